@@ -1,8 +1,49 @@
 use alloc::vec::Vec;
+use alloc::sync::Arc;
+use alloc::string::String;
 use rand::{rngs::StdRng, SeedableRng, RngCore};
 
+use spin::Mutex;
+use async_trait::async_trait;
+
 use crate::vnix::utils::Maybe;
-use crate::vnix::core::driver::{DispErr, Disp, Rnd, RndErr, Mouse};
+use crate::vnix::core::user::Usr;
+use crate::vnix::core::driver::{CLI, CLIErr, TermKey, DispErr, Disp, Time, TimeErr, TimeUnit, Duration, Rnd, RndErr, Mem, MemErr, MemSizeUnits, Mouse, Disk, DiskErr, Net, NetErr};
+
+// terminal without input, output is dropped
+pub struct StubCLI;
+
+impl core::fmt::Write for StubCLI {
+    fn write_str(&mut self, _s: &str) -> core::fmt::Result {
+        Ok(())
+    }
+}
+
+impl CLI for StubCLI {
+    fn res(&self) -> Result<(usize, usize), CLIErr> {
+        Ok((80, 25))
+    }
+
+    fn res_list(&self) -> Result<Vec<(usize, usize)>, CLIErr> {
+        Ok(Vec::from([(80, 25)]))
+    }
+
+    fn set_res(&mut self, _res: (usize, usize)) -> Result<(), CLIErr> {
+        Ok(())
+    }
+
+    fn glyth(&mut self, _ch: char, _pos: (usize, usize)) -> Result<(), CLIErr> {
+        Ok(())
+    }
+
+    fn get_key(&mut self, _block: bool) -> Maybe<TermKey, CLIErr> {
+        Ok(None)
+    }
+
+    fn clear(&mut self) -> Result<(), CLIErr> {
+        Ok(())
+    }
+}
 
 pub struct StubDisp;
 
@@ -44,6 +85,55 @@ impl Disp for StubDisp {
     }
 }
 
+// virtual clock in microseconds, waiting just moves it forward
+#[derive(Debug, Default)]
+pub struct StubTime(pub u128);
+
+#[async_trait(?Send)]
+impl Time for StubTime {
+    fn start(&mut self) -> Result<(), TimeErr> {
+        Ok(())
+    }
+
+    fn wait(&mut self, dur: Duration) -> Result<(), TimeErr> {
+        self.0 += match dur {
+            Duration::Micro(mcs) => mcs as u128,
+            Duration::Milli(ms) => ms as u128 * 1000,
+            Duration::Seconds(sec) => sec as u128 * 1000000
+        };
+        Ok(())
+    }
+
+    async fn wait_async(&self, _dur: Duration) -> Result<(), TimeErr> {
+        Ok(())
+    }
+
+    fn uptime(&self, units: TimeUnit) -> Result<u128, TimeErr> {
+        let sec = self.0 / 1000000;
+
+        let time = match units {
+            TimeUnit::Micro => self.0,
+            TimeUnit::Milli => self.0 / 1000,
+            TimeUnit::Second => sec,
+            TimeUnit::Minute => sec / 60,
+            TimeUnit::Hour => sec / (60 * 60),
+            TimeUnit::Day => sec / (24 * 60 * 60),
+            TimeUnit::Week => sec / (7 * 24 * 60 * 60),
+            TimeUnit::Month => sec / (4 * 7 * 24 * 60 * 60),
+            TimeUnit::Year => sec / (12 * 4 * 7 * 24 * 60 * 60)
+        };
+        Ok(time)
+    }
+}
+
+pub struct StubMem;
+
+impl Mem for StubMem {
+    fn free(&self, _units: MemSizeUnits) -> Result<usize, MemErr> {
+        Ok(0)
+    }
+}
+
 pub struct PRng(pub [u8; 32]);
 
 impl Rnd for PRng {
@@ -51,8 +141,93 @@ impl Rnd for PRng {
         let mut rng = StdRng::from_seed(self.0);
 
         rng.fill_bytes(buf);
-        self.0 = buf[0..32].try_into().map_err(|_| RndErr::GetBytes)?;
 
+        // next seed, buffer may be shorter than it
+        rng.fill_bytes(&mut self.0);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StubDisk {
+    pub blobs: Vec<(String, Vec<u8>)>
+}
+
+impl Disk for StubDisk {
+    fn read(&self, name: &str) -> Maybe<Vec<u8>, DiskErr> {
+        Ok(self.blobs.iter().find(|(n, _)| n == name).map(|(_, dat)| dat.clone()))
+    }
+
+    fn write(&mut self, name: &str, dat: &[u8]) -> Result<(), DiskErr> {
+        if let Some((_, blob)) = self.blobs.iter_mut().find(|(n, _)| n == name) {
+            *blob = dat.to_vec();
+        } else {
+            self.blobs.push((name.into(), dat.to_vec()));
+        }
         Ok(())
     }
+
+    fn list(&self) -> Result<Vec<String>, DiskErr> {
+        Ok(self.blobs.iter().map(|(n, _)| n.clone()).collect())
+    }
+}
+
+// in-memory network between kernels in one process
+#[derive(Debug, Default)]
+pub struct LoopHub {
+    nodes: Vec<[u16; 8]>,
+    frames: Vec<([u16; 8], [u16; 8], Vec<u8>)>
+}
+
+pub struct LoopNet {
+    addr: [u16; 8],
+    hub: Arc<Mutex<LoopHub>>
+}
+
+impl LoopNet {
+    pub fn new(addr: [u16; 8], hub: Arc<Mutex<LoopHub>>) -> Self {
+        LoopNet {addr, hub}
+    }
+}
+
+impl Net for LoopNet {
+    fn addr(&self) -> [u16; 8] {
+        self.addr
+    }
+
+    // hub is trusted, frames are still checked by kernel
+    fn auth(&mut self, _node: &Usr, _peers: &[([u16; 8], String)]) {}
+
+    fn listen(&mut self) -> Result<(), NetErr> {
+        let mut hub = self.hub.lock();
+
+        if hub.nodes.contains(&self.addr) {
+            return Err(NetErr::Listen)
+        }
+
+        hub.nodes.push(self.addr);
+        Ok(())
+    }
+
+    fn connect(&mut self, addr: [u16; 8]) -> Result<(), NetErr> {
+        if !self.hub.lock().nodes.contains(&addr) {
+            return Err(NetErr::Unreachable)
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, addr: [u16; 8], dat: &[u8]) -> Result<(), NetErr> {
+        self.connect(addr)?;
+
+        self.hub.lock().frames.push((addr, self.addr, dat.to_vec()));
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Maybe<([u16; 8], Vec<u8>), NetErr> {
+        let mut hub = self.hub.lock();
+        let frame = hub.frames.extract_if(|(dst, _, _)| *dst == self.addr).next();
+
+        Ok(frame.map(|(_, src, dat)| (src, dat)))
+    }
 }
@@ -1,10 +1,13 @@
 use core::fmt::{Write, Display};
 
 use alloc::vec::Vec;
+use alloc::string::String;
 
 use async_trait::async_trait;
 use crate::vnix::utils::Maybe;
 
+use super::user::Usr;
+
 
 #[derive(Debug)]
 pub enum CLIErr {
@@ -40,6 +43,24 @@ pub enum MemErr {
     NotEnough
 }
 
+#[derive(Debug)]
+pub enum DiskErr {
+    Read,
+    Write,
+    List
+}
+
+#[derive(Debug)]
+pub enum NetErr {
+    Listen,
+    Connect,
+    Send,
+    Recv,
+    Unreachable,
+    Pending, // connection is not ready yet, frame can be sent a bit later
+    Timeout
+}
+
 #[derive(Debug)]
 pub enum DrvErr {
     DriverFault,
@@ -48,6 +69,8 @@ pub enum DrvErr {
     Time(TimeErr),
     Rnd(RndErr),
     Mem(MemErr),
+    Disk(DiskErr),
+    Net(NetErr),
 }
 
 #[derive(Debug, PartialEq)]
@@ -138,6 +161,22 @@ pub trait Mem {
     fn free(&self, units: MemSizeUnits) -> Result<usize, MemErr>;
 }
 
+pub trait Disk {
+    fn read(&self, name: &str) -> Maybe<Vec<u8>, DiskErr>;
+    fn write(&mut self, name: &str, dat: &[u8]) -> Result<(), DiskErr>;
+    fn list(&self) -> Result<Vec<String>, DiskErr>;
+}
+
+pub trait Net {
+    fn addr(&self) -> [u16; 8];
+    // node key proves this node to others, connections are accepted only from known nodes
+    fn auth(&mut self, node: &Usr, peers: &[([u16; 8], String)]);
+    fn listen(&mut self) -> Result<(), NetErr>;
+    fn connect(&mut self, addr: [u16; 8]) -> Result<(), NetErr>;
+    fn send(&mut self, addr: [u16; 8], dat: &[u8]) -> Result<(), NetErr>;
+    fn recv(&mut self) -> Maybe<([u16; 8], Vec<u8>), NetErr>;
+}
+
 impl Display for TermKey {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -1,31 +1,62 @@
 use core::fmt::{Display, Write};
 
 use futures::future;
-use futures::executor::block_on;
+use futures::task::{waker, Context, Poll, Waker};
 
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use alloc::format;
 use alloc::boxed::Box;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use num::{BigInt, BigRational};
 
 use super::msg::Msg;
+use super::wire::{NetFrame, NetPending, NET_QUEUE_NAME};
 use super::user::Usr;
-use super::task::{Task, TaskRun, TaskSig};
+use super::policy::{Policy, POLICY_PATH};
+use super::peer::{Peer, NET_PATH, PEERS_PATH, HIDDEN_PATH, EXPORTED_PATH, PRIVATE_SERV, NODE_KEY_NAME, NODE_USR};
+use super::task::{Task, TaskRun, TaskRunAsync, TaskSig, TaskPrio, TaskWake, TaskWait, Yield};
 use super::unit::{Unit, UnitParseErr, UnitAs, UnitNew, Path, UnitBase, Int, Dec};
 use super::serv::{Serv, ServErr, ServResult};
-use super::driver::{CLIErr, CLI, Disp, Time, Rnd, Mem, DrvErr};
+use super::driver::{CLIErr, CLI, Disp, Time, TimeUnit, Duration, Rnd, Mem, Disk, Net, NetErr, DrvErr};
 
 use crate::vnix::serv::io::term::base;
-use crate::vnix::utils::RamStore;
+use crate::vnix::utils::{RamStore, StoreAcl, StoreRepl, StoreFmt, STORE_NAME, STORE_ACL_NAME, STORE_REPL_NAME};
 
 use spin::Mutex;
 
 
+// how often idle kernel checks input and network, in microseconds
+const IDLE_TICK: u128 = 10000;
+
+// unclaimed task results are dropped when table is full or after timeout in microseconds
+const TASK_RESULT_MAX: usize = 256;
+const TASK_RESULT_TTL: u128 = 60000000;
+
+// request to other node fails if reply doesn't come in time, in microseconds
+const NET_REPLY_TIMEOUT: u128 = 30000000;
+
+// queued message is dropped after this many tries, about an hour
+const NET_RETRY_MAX: u32 = 64;
+
+// nonces of last received frames, replayed frame is dropped
+const NET_NONCE_MAX: usize = 1024;
+
+fn micros(dur: Duration) -> u128 {
+    match dur {
+        Duration::Micro(mcs) => mcs as u128,
+        Duration::Milli(ms) => ms as u128 * 1000,
+        Duration::Seconds(sec) => sec as u128 * 1000000
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Addr {
     Local,
-    Remote([u16; 8])
+    Remote([u16; 8]),
+    Node(String),
+    Route(Vec<Addr>)
 }
 
 #[derive(Debug)]
@@ -40,6 +71,8 @@ pub enum KernErr {
     SignFault,
     SignVerifyFault,
     HashVerifyFault,
+    EncryptFault,
+    DecryptFault,
     UsrNotFound,
     UsrNameAlreadyReg,
     UsrAlreadyReg,
@@ -47,11 +80,27 @@ pub enum KernErr {
     ServNotFound,
     ServAlreadyReg,
     CannotCreateServInstance,
+    ServAccessDenied,
+    ServNotExported,
+    PolicyFault,
     TaskAlreadyReg,
     TaskNotFound,
+    TaskKilled,
+    TaskResultDropped,
+    TaskTimeout,
     DbLoadFault,
     DbSaveFault,
+    DbFormatFault,
+    DbTxnFault(usize, Box<KernErr>),
+    DbAccessDenied,
     HelpTopicNotFound,
+    RemoteFault(String),
+    NetMsgNotFound,
+    NetMsgDropped,
+    NodeNotFound,
+    NodeAlreadyReg,
+    NodeFault,
+    WireFault,
     ParseErr(UnitParseErr),
     DrvErr(DrvErr),
     ServErr(ServErr)
@@ -63,6 +112,8 @@ pub struct KernDrv {
     pub time: Box<dyn Time>,
     pub rnd: Box<dyn Rnd>,
     pub mem: Box<dyn Mem>,
+    pub disk: Box<dyn Disk>,
+    pub net: Box<dyn Net>,
 }
 
 struct KernDataPool {
@@ -80,6 +131,10 @@ pub struct Kern {
     pub drv: KernDrv,
     pub term: Rc<Mutex<base::Term>>,
     pub ram_store: RamStore,
+    pub policy: Policy,
+    pub peers: Vec<Peer>,
+    pub net_hidden: Vec<String>,
+    pub net_exported: Vec<String>,
     
     // vnix
     users: Vec<Usr>,
@@ -88,11 +143,30 @@ pub struct Kern {
     data_pool: KernDataPool,
 
     last_task_id: usize,
-    curr_task_id: usize,
+    curr_task_id: Option<usize>, // none outside of task poll
     tasks_queue: Vec<Task>,
     tasks_running: Vec<Task>,
     tasks_signals: Vec<(usize, TaskSig)>,
-    task_result: Vec<(usize, ServResult)>
+    tasks_stopped: Vec<usize>,
+    task_result: Vec<(usize, usize, u128, ServResult)>,
+    task_result_dropped: usize,
+    tasks_detached: Vec<usize>,
+    tasks_deadline: Vec<(usize, u128)>,
+    tasks_timedout: Vec<usize>,
+    tasks_waiting: Vec<(TaskWait, Waker)>,
+    next_tick: u128,
+    budget: usize,
+
+    // network
+    node: Option<Usr>,
+    last_net_id: usize,
+    net_sent: Vec<(usize, [u16; 8], u128)>,
+    net_nonces: Vec<u64>,
+    net_result: Vec<(usize, u128, ServResult)>,
+    net_serving: Vec<(usize, [u16; 8], usize, Option<String>)>,
+    net_relaying: Vec<(usize, [u16; 8], usize)>,
+    net_detached: Vec<usize>,
+    pub net_queue: Vec<NetPending>
 }
 
 impl Display for Addr {
@@ -103,7 +177,9 @@ impl Display for Addr {
                 "{:#04x}:{:#04x}:{:#04x}:{:#04x}:{:#04x}:{:#04x}:{:#04x}:{:#04x}",
                 addr[0], addr[1], addr[2], addr[3],
                 addr[4], addr[5], addr[6], addr[7]
-            )
+            ),
+            Addr::Node(name) => write!(f, "{name}"),
+            Addr::Route(route) => write!(f, "{}", route.iter().map(|hop| format!("{hop}")).collect::<Vec<_>>().join(">"))
         }
     }
 }
@@ -117,13 +193,15 @@ impl Write for Kern {
 }
 
 impl KernDrv {
-    pub fn new(cli: Box<dyn CLI>, disp: Box<dyn Disp>, time: Box<dyn Time>, rnd: Box<dyn Rnd>, mem: Box<dyn Mem>) -> Self {
+    pub fn new(cli: Box<dyn CLI>, disp: Box<dyn Disp>, time: Box<dyn Time>, rnd: Box<dyn Rnd>, mem: Box<dyn Mem>, disk: Box<dyn Disk>, net: Box<dyn Net>) -> Self {
         KernDrv {
             cli,
             disp,
             time,
             rnd,
-            mem
+            mem,
+            disk,
+            net
         }
     }
 }
@@ -269,16 +347,37 @@ impl Kern {
         let kern = Kern {
             drv,
             ram_store: RamStore::default(),
+            policy: Policy::default(),
+            peers: Vec::new(),
+            net_hidden: Vec::new(),
+            net_exported: Vec::new(),
             term,
             users: Vec::new(),
             services: Vec::new(),
             data_pool: KernDataPool::new(),
             last_task_id: 0,
-            curr_task_id: 0,
+            curr_task_id: None,
             tasks_queue: Vec::new(),
             tasks_running: Vec::new(),
             tasks_signals: Vec::new(),
-            task_result: Vec::new()
+            tasks_stopped: Vec::new(),
+            task_result: Vec::new(),
+            task_result_dropped: 0,
+            tasks_detached: Vec::new(),
+            tasks_deadline: Vec::new(),
+            tasks_timedout: Vec::new(),
+            tasks_waiting: Vec::new(),
+            next_tick: 0,
+            budget: 0,
+            node: None,
+            last_net_id: 0,
+            net_sent: Vec::new(),
+            net_nonces: Vec::new(),
+            net_result: Vec::new(),
+            net_serving: Vec::new(),
+            net_relaying: Vec::new(),
+            net_detached: Vec::new(),
+            net_queue: Vec::new()
         };
 
         kern
@@ -288,6 +387,181 @@ impl Kern {
         self.data_pool.new_or_get(u)
     }
 
+    pub fn load_store(&mut self) -> Result<(), KernErr> {
+        let dat = self.drv.disk.read(STORE_NAME).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))?.ok_or(KernErr::DbLoadFault)?;
+        let (store, fmt) = StoreFmt::decode(&dat)?;
+
+        self.ram_store.data = self.new_unit(store);
+        self.ram_store.fmt = fmt;
+
+        // access rules
+        if let Some(dat) = self.drv.disk.read(STORE_ACL_NAME).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))? {
+            let acl = StoreFmt::decode(&dat)?.0.as_list().ok_or(KernErr::DbFormatFault)?;
+            self.ram_store.acl = acl.iter().map(|u| StoreAcl::from_unit(u.clone())).collect::<Option<Vec<_>>>().ok_or(KernErr::DbFormatFault)?;
+        }
+
+        // replication state
+        if let Some(dat) = self.drv.disk.read(STORE_REPL_NAME).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))? {
+            self.ram_store.repl = StoreRepl::from_unit(StoreFmt::decode(&dat)?.0).ok_or(KernErr::DbFormatFault)?;
+        }
+
+        self.ram_store.dirty = false;
+
+        Ok(())
+    }
+
+    pub fn load_policy(&mut self) -> Result<(), KernErr> {
+        let path = POLICY_PATH.map(|s| s.to_string()).to_vec();
+
+        // policy must be written by super, ignore others
+        if !self.ram_store.owned_by(RamStore::SUPER, &path) {
+            return Ok(())
+        }
+
+        if let Some(u) = self.ram_store.load(Unit::path(&POLICY_PATH)) {
+            self.policy = Policy::from_unit(u).ok_or(KernErr::PolicyFault)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_net(&mut self) -> Result<(), KernErr> {
+        let path = NET_PATH.map(|s| s.to_string()).to_vec();
+
+        // network config must be written by super, ignore others
+        let owned = self.ram_store.owned_by(RamStore::SUPER, &path);
+
+        if let Some(u) = self.ram_store.load(Unit::path(&PEERS_PATH)).filter(|_| owned) {
+            let peers = Peer::list_from_unit(u).ok_or(KernErr::NodeFault)?;
+            self.set_peers(peers)?;
+        }
+
+        let names = |u: Unit| u.as_list()?.iter().map(|u| u.clone().as_str().map(|s| s.to_string())).collect::<Option<Vec<_>>>();
+
+        if let Some(u) = self.ram_store.load(Unit::path(&HIDDEN_PATH)).filter(|_| owned) {
+            self.net_hidden = names(u).ok_or(KernErr::NodeFault)?;
+        }
+
+        if let Some(u) = self.ram_store.load(Unit::path(&EXPORTED_PATH)).filter(|_| owned) {
+            self.net_exported = names(u).ok_or(KernErr::NodeFault)?;
+        }
+
+        // pending messages
+        if let Some(dat) = self.drv.disk.read(NET_QUEUE_NAME).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))? {
+            self.net_queue = NetPending::decode_queue(&dat)?;
+            self.last_net_id = self.net_queue.iter().map(|p| p.id).max().unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    // key pair of this node, created on first boot
+    pub fn load_node(&mut self) -> Result<(), KernErr> {
+        let node = match self.drv.disk.read(NODE_KEY_NAME).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))? {
+            Some(dat) => {
+                let u = StoreFmt::decode(&dat)?.0;
+                let key = |k: &str| u.clone().as_map_find(k).and_then(|u| u.as_str()).ok_or(KernErr::NodeFault);
+
+                Usr::login(NODE_USR, &key("priv")?, &key("pub")?, &key("priv.enc")?, &key("pub.enc")?)?
+            },
+            None => {
+                let (node, out) = Usr::new(NODE_USR, self)?;
+                self.drv.disk.write(NODE_KEY_NAME, out.as_bytes()).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))?;
+                node
+            }
+        };
+
+        self.node = Some(node);
+        self.net_auth();
+
+        Ok(())
+    }
+
+    fn net_auth(&mut self) {
+        let peers = self.peers.iter().map(|p| (p.addr, p.key.clone())).collect::<Vec<_>>();
+
+        if let Some(node) = &self.node {
+            self.drv.net.auth(node, &peers);
+        }
+
+        // connect ahead, so nodes are reachable when needed
+        for (addr, _) in peers {
+            self.drv.net.connect(addr).ok();
+        }
+    }
+
+    pub fn get_node(&self) -> Result<&Usr, KernErr> {
+        self.node.as_ref().ok_or(KernErr::NodeFault)
+    }
+
+    // node addresses to pass through, empty for this node
+    pub fn resolve(&self, addr: &Addr) -> Result<Vec<[u16; 8]>, KernErr> {
+        let route = match addr {
+            Addr::Local => Vec::new(),
+            Addr::Remote(addr) => Vec::from([*addr]),
+            Addr::Node(name) => Vec::from([self.get_peer(name)?.addr]),
+            Addr::Route(route) => route.iter().map(|hop| self.resolve(hop)).collect::<Result<Vec<_>, _>>()?.concat()
+        };
+
+        let own = self.drv.net.addr();
+        Ok(route.into_iter().filter(|addr| *addr != own).collect())
+    }
+
+    // system services and storage are exported only explicitly, others unless hidden
+    pub fn is_exported(&self, serv: &str) -> bool {
+        if self.net_exported.iter().any(|s| s == serv) {
+            return true
+        }
+
+        let private = PRIVATE_SERV.iter().any(|p| p.strip_suffix("*").map_or(*p == serv, |pref| serv.starts_with(pref)));
+        !private && self.net_hidden.iter().all(|s| s != serv)
+    }
+
+    // every peer has local user with own keys, requests of users unknown here run as it
+    pub fn set_peers(&mut self, peers: Vec<Peer>) -> Result<(), KernErr> {
+        let is_proxy = |name: &str| self.peers.iter().any(|p| p.name == name);
+
+        if peers.iter().any(|p| self.users.iter().any(|u| u.name == p.name) && !is_proxy(&p.name)) {
+            return Err(KernErr::UsrNameAlreadyReg)
+        }
+
+        let old = core::mem::replace(&mut self.peers, peers);
+        self.users.retain(|u| !old.iter().any(|p| p.name == u.name));
+
+        for i in 0..self.peers.len() {
+            let usr = Usr::new(&self.peers[i].name.clone(), self)?.0;
+            self.reg_usr(usr)?;
+        }
+
+        self.net_auth();
+        Ok(())
+    }
+
+    pub fn get_peer(&self, name: &str) -> Result<&Peer, KernErr> {
+        self.peers.iter().find(|p| p.name == name).ok_or(KernErr::NodeNotFound)
+    }
+
+    pub fn sync_store(&mut self) -> Result<(), KernErr> {
+        // write-through
+        if self.ram_store.sync && self.ram_store.dirty {
+            return self.flush_store();
+        }
+        Ok(())
+    }
+
+    pub fn flush_store(&mut self) -> Result<(), KernErr> {
+        let dat = self.ram_store.fmt.encode(self.ram_store.data.clone())?;
+        self.drv.disk.write(STORE_NAME, &dat).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))?;
+
+        let acl = Unit::list(&self.ram_store.acl.iter().map(|acl| acl.as_unit()).collect::<Vec<_>>());
+        let dat = self.ram_store.fmt.encode(acl)?;
+        self.drv.disk.write(STORE_ACL_NAME, &dat).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))?;
+
+        let dat = self.ram_store.fmt.encode(self.ram_store.repl.as_unit())?;
+        self.drv.disk.write(STORE_REPL_NAME, &dat).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))?;
+
+        self.ram_store.dirty = false;
+        Ok(())
+    }
+
     pub fn reg_usr(&mut self, usr: Usr) -> Result<(), KernErr> {
         if self.users.iter().find(|u| u.name == usr.name && u.pub_key != usr.pub_key).is_some() {
             return Err(KernErr::UsrNameAlreadyReg);
@@ -305,7 +579,7 @@ impl Kern {
         Ok(())
     }
 
-    fn get_usr(&self, ath: &str) -> Result<Usr, KernErr> {
+    pub fn get_usr(&self, ath: &str) -> Result<Usr, KernErr> {
         self.users.iter().find(|usr| usr.name == ath).ok_or(KernErr::UsrNotFound).cloned()
     }
 
@@ -319,13 +593,154 @@ impl Kern {
     }
 
     pub fn reg_task(&mut self, usr: &str, name: &str, run: TaskRun) -> Result<usize, KernErr> {
-        self.tasks_queue.push(Task::new(usr.into(), name.into(), self.last_task_id, self.curr_task_id, run));
+        // terminal tasks are interactive, others inherit priority of parent
+        let prio = if run.1 == "io.term" {
+            TaskPrio::High
+        } else {
+            self.tasks_running.iter().find(|t| Some(t.id) == self.curr_task_id).map_or(TaskPrio::Norm, |t| t.prio)
+        };
+
+        // task registered by kernel itself is own parent, like init task
+        let parent_id = self.curr_task_id.unwrap_or(self.last_task_id);
+
+        self.tasks_queue.push(Task::new(usr.into(), name.into(), self.last_task_id, parent_id, prio, run));
         self.last_task_id += 1;
         Ok(self.last_task_id - 1)
     }
 
+    // run task without keeping its result
+    pub fn reg_task_detached(&mut self, usr: &str, name: &str, run: TaskRun) -> Result<usize, KernErr> {
+        let id = self.reg_task(usr, name, run)?;
+        self.tasks_detached.push(id);
+
+        Ok(id)
+    }
+
     pub fn task_sig(&mut self, id: usize, sig: TaskSig) -> Result<(), KernErr> {
-        self.tasks_signals.push((id, sig));
+        if self.tasks_running.iter().any(|t| t.id == id) {
+            self.tasks_signals.push((id, sig));
+            return Ok(())
+        }
+
+        if !self.tasks_queue.iter().any(|t| t.id == id) {
+            return Err(KernErr::TaskNotFound)
+        }
+
+        // not started yet: kill dequeues, stopped task is held when it starts
+        match sig {
+            TaskSig::Kill => self.task_kill(id)?,
+            TaskSig::Stop => if !self.tasks_stopped.contains(&id) {
+                self.tasks_stopped.push(id);
+            },
+            TaskSig::Cont => self.tasks_stopped.retain(|i| *i != id),
+            TaskSig::Usr(..) => self.tasks_signals.push((id, sig))
+        }
+        Ok(())
+    }
+
+    // kill children too, waiters get error
+    fn task_kill(&mut self, id: usize) -> Result<(), KernErr> {
+        let running = self.tasks_running.extract_if(|t| t.id == id).next();
+        let queued = self.tasks_queue.extract_if(|t| t.id == id).next();
+        let parent = running.or(queued).map_or(id, |t| t.parent_id);
+
+        let childs = self.tasks_running.iter().filter(|t| t.parent_id == id && t.id != id).map(|t| t.id).collect::<Vec<_>>();
+
+        for child in childs {
+            self.tasks_signals.push((child, TaskSig::Kill));
+        }
+
+        // not started yet, with whole subtree
+        let mut killed = vec![(id, parent)];
+        let mut i = 0;
+
+        while let Some(&(id, _)) = killed.get(i) {
+            let queued = self.tasks_queue.extract_if(|t| t.parent_id == id).map(|t| (t.id, t.parent_id)).collect::<Vec<_>>();
+            killed.extend(queued);
+            i += 1;
+        }
+
+        for (id, parent) in killed.into_iter().rev() {
+            self.tasks_signals.retain(|(i, _)| *i != id);
+            self.tasks_stopped.retain(|i| *i != id);
+            self.tasks_deadline.retain(|(i, _)| *i != id);
+
+            let res = if self.tasks_timedout.extract_if(|i| *i == id).next().is_some() {
+                Err(KernErr::TaskTimeout)
+            } else {
+                Err(KernErr::TaskKilled)
+            };
+            self.task_done(id, parent, res)?;
+        }
+        Ok(())
+    }
+
+    // kill task with its subtree, when deadline passes
+    pub fn task_timeout(&mut self, id: usize, dur: Duration) -> Result<(), KernErr> {
+        if !self.tasks_running.iter().chain(self.tasks_queue.iter()).any(|t| t.id == id) {
+            return Err(KernErr::TaskNotFound)
+        }
+
+        let until = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))? + micros(dur);
+        self.tasks_deadline.push((id, until));
+
+        Ok(())
+    }
+
+    // nobody waits for result of detached task
+    fn task_done(&mut self, id: usize, parent: usize, res: ServResult) -> Result<(), KernErr> {
+        if self.tasks_detached.extract_if(|i| *i == id).next().is_some() {
+            return Ok(())
+        }
+
+        let now = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+
+        self.task_result.push((id, parent, now, res));
+        self.wake(|w| *w == TaskWait::Task(id));
+
+        // drop oldest of unclaimed
+        if self.task_result.len() > TASK_RESULT_MAX {
+            let cnt = self.task_result.len() - TASK_RESULT_MAX;
+            let drop = self.task_result_unclaimed().into_iter().take(cnt).collect::<Vec<_>>();
+
+            self.task_result.retain(|(id, _, _, _)| !drop.contains(id));
+            self.task_result_dropped += drop.len();
+        }
+        Ok(())
+    }
+
+    // results nobody can claim anymore: parent is gone and request is not served to other node
+    fn task_result_unclaimed(&self) -> Vec<usize> {
+        self.task_result.iter()
+            .filter(|(id, parent, _, _)| {
+                !self.net_serving.iter().any(|(i, _, _, _)| i == id) && !self.tasks_running.iter().chain(self.tasks_queue.iter()).any(|t| t.id == *parent)
+            })
+            .map(|(id, _, _, _)| *id)
+            .collect()
+    }
+
+    // unclaimed and dropped results
+    pub fn task_result_stat(&self) -> (usize, usize) {
+        (self.task_result.len(), self.task_result_dropped)
+    }
+
+    // user signal sent to current task
+    pub fn take_sig(&mut self, sig: &str) -> bool {
+        let id = match self.curr_task_id {
+            Some(id) => id,
+            None => return false
+        };
+
+        self.tasks_signals.extract_if(move |(i, s)| *i == id && matches!(s, TaskSig::Usr(s) if s == sig)).next().is_some()
+    }
+
+    pub fn is_task_stopped(&self, id: usize) -> bool {
+        self.tasks_stopped.contains(&id)
+    }
+
+    pub fn task_prio(&mut self, id: usize, prio: TaskPrio) -> Result<(), KernErr> {
+        let task = self.tasks_running.iter_mut().chain(self.tasks_queue.iter_mut()).find(|t| t.id == id).ok_or(KernErr::TaskNotFound)?;
+        task.prio = prio;
         Ok(())
     }
 
@@ -337,12 +752,133 @@ impl Kern {
         self.tasks_running.clone()
     }
 
+    // running or not started yet
+    pub fn get_task(&self, id: usize) -> Option<Task> {
+        self.tasks_running.iter().chain(self.tasks_queue.iter()).find(|t| t.id == id).cloned()
+    }
+
     pub fn get_task_running(&self) -> Option<Task> {
-        self.tasks_running.iter().find(|t| t.id == self.curr_task_id).map(|t| t.clone())
+        self.tasks_running.iter().find(|t| Some(t.id) == self.curr_task_id).map(|t| t.clone())
     }
 
     pub fn get_task_result(&mut self, id: usize) -> Option<ServResult> {
-        self.task_result.extract_if(|(i, _)| *i == id).next().map(|(_, msg)| msg)
+        self.task_result.extract_if(|(i, _, _, _)| *i == id).next().map(|(_, _, _, msg)| msg)
+    }
+
+    // register waker of blocked task
+    fn wait(&mut self, on: TaskWait, waker: &Waker) {
+        if !self.tasks_waiting.iter().any(|(w, _waker)| *w == on && _waker.will_wake(waker)) {
+            self.tasks_waiting.push((on, waker.clone()));
+        }
+    }
+
+    fn wake(&mut self, f: impl Fn(&TaskWait) -> bool) {
+        for (_, waker) in self.tasks_waiting.extract_if(|(w, _)| f(w)) {
+            waker.wake();
+        }
+    }
+
+    // wake expired timers and input waiters on every tick
+    fn wake_timers(&mut self) -> Result<(), KernErr> {
+        let now = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+        self.wake(|w| matches!(w, TaskWait::Time(t) if *t <= now));
+
+        if now >= self.next_tick {
+            self.next_tick = now + IDLE_TICK;
+            self.wake(|w| *w == TaskWait::Input);
+        }
+
+        // deadlines
+        for (id, _) in self.tasks_deadline.extract_if(|(_, t)| *t <= now).collect::<Vec<_>>() {
+            self.tasks_signals.push((id, TaskSig::Kill));
+            self.tasks_timedout.push(id);
+        }
+
+        // expire unclaimed results
+        let unclaimed = self.task_result_unclaimed();
+        let cnt = self.task_result.extract_if(|(id, _, t, _)| *t + TASK_RESULT_TTL <= now && unclaimed.contains(id)).count();
+
+        self.task_result_dropped += cnt;
+
+        self.net_expire(now)
+    }
+
+    // sleep until next tick or timer
+    fn idle(&mut self) -> Result<(), KernErr> {
+        let now = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+
+        let until = self.tasks_waiting.iter().filter_map(|(w, _)| match w {
+            TaskWait::Time(t) => Some(*t),
+            _ => None
+        }).chain(self.tasks_deadline.iter().map(|(_, t)| *t)).fold(self.next_tick, |a, b| a.min(b));
+
+        if until > now {
+            self.drv.time.wait(Duration::Micro((until - now) as usize)).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+        }
+        Ok(())
+    }
+
+    // spend budget of current task, yield when it is over
+    pub async fn coop(mtx: &Mutex<Self>) {
+        {
+            let mut grd = mtx.lock();
+
+            if grd.budget > 0 {
+                grd.budget -= 1;
+                return
+            }
+        }
+        Yield::now().await
+    }
+
+    pub async fn task_wait(mtx: &Mutex<Self>, id: usize) -> ServResult {
+        future::poll_fn(|cx| {
+            let mut grd = mtx.lock();
+
+            if let Some(res) = grd.get_task_result(id) {
+                return Poll::Ready(res)
+            }
+
+            // result was dropped
+            if !grd.tasks_running.iter().chain(grd.tasks_queue.iter()).any(|t| t.id == id) {
+                return Poll::Ready(Err(KernErr::TaskResultDropped))
+            }
+
+            grd.wait(TaskWait::Task(id), cx.waker());
+            Poll::Pending
+        }).await
+    }
+
+    pub async fn sleep(mtx: &Mutex<Self>, dur: Duration) -> Result<(), KernErr> {
+        let until = mtx.lock().drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))? + micros(dur);
+
+        future::poll_fn(|cx| {
+            let mut grd = mtx.lock();
+
+            match grd.drv.time.uptime(TimeUnit::Micro) {
+                Ok(now) if now < until => (),
+                Ok(..) => return Poll::Ready(Ok(())),
+                Err(e) => return Poll::Ready(Err(KernErr::DrvErr(DrvErr::Time(e))))
+            }
+
+            grd.wait(TaskWait::Time(until), cx.waker());
+            Poll::Pending
+        }).await
+    }
+
+    // wait for next tick, then task polls input again
+    pub async fn input_wait(mtx: &Mutex<Self>) {
+        let mut ticked = false;
+
+        future::poll_fn(|cx| {
+            if ticked {
+                return Poll::Ready(())
+            }
+
+            ticked = true;
+            mtx.lock().wait(TaskWait::Input, cx.waker());
+            Poll::Pending
+        }).await
     }
 
     pub fn msg(&mut self, ath: &str, u: Unit) -> Result<Msg, KernErr> {
@@ -350,23 +886,53 @@ impl Kern {
         Msg::new(usr, self.new_unit(u))
     }
 
+    // encrypt unit by recipient key, signature covers encrypted unit and recipient
+    pub fn msg_enc(&mut self, ath: &str, to: &str, u: Unit) -> Result<Msg, KernErr> {
+        let usr = self.get_usr(ath)?;
+        let enc = self.get_usr(to)?.encrypt(u, self)?;
+
+        Msg::new_enc(usr, to, Unit::str(&enc))
+    }
+
+    // decrypt message by recipient key, service opens it only if recipient is logged in here
+    pub fn open_msg(&mut self, msg: &Msg) -> Result<Unit, KernErr> {
+        let to = match &msg.to {
+            Some(to) => to,
+            None => return Ok(msg.msg.clone())
+        };
+
+        let enc = msg.msg.clone().as_str().ok_or(KernErr::DecryptFault)?;
+        let u = self.get_usr(to)?.decrypt(&enc)?;
+
+        Ok(self.new_unit(u))
+    }
+
+    pub fn serv_list(&self, exported: bool) -> Unit {
+        let serv = self.services.iter()
+            .filter(|serv| !exported || self.is_exported(&serv.info.name))
+            .map(|serv| Unit::str(&serv.info.name))
+            .collect::<Vec<_>>();
+
+        Unit::list(&serv)
+    }
+
     async fn help_serv(kern: &Mutex<Self>, ath: String) -> ServResult {
-        let serv = kern.lock().services.iter().map(|serv| Unit::str(&serv.info.name)).collect::<Vec<_>>();
+        let serv = kern.lock().serv_list(false);
         
         let u = Unit::map(&[(
             Unit::str("msg"),
-            Unit::list(&serv)
+            serv
         )]);    
         kern.lock().msg(&ath, u).map(|m| Some(m))
     }
 
     pub async fn send(mtx: &Mutex<Self>, serv: String, msg: Msg) -> ServResult {
-        // verify msg
+        // verify msg, encrypted one is passed to service as is
         let usr = mtx.lock().get_usr(&msg.ath)?;
-        usr.verify(msg.msg.clone(), &msg.sign, &msg.hash)?;
+        usr.verify(msg.body(), &msg.sign, &msg.hash)?;
 
         // check help
-        if let Some(s) = msg.msg.clone().as_str() {
+        if let Some(s) = msg.msg.clone().as_str().filter(|_| msg.to.is_none()) {
             match s.as_str() {
                 "serv" => return Self::help_serv(mtx, msg.ath.clone()).await,
                 _ => if s.starts_with("help") {
@@ -381,6 +947,13 @@ impl Kern {
             }
         }
 
+        // check policy
+        let body = Some(&msg.msg).filter(|_| msg.to.is_none());
+
+        if msg.ath != "super" && !mtx.lock().policy.check(&msg.ath, &serv, body) {
+            return Err(KernErr::ServAccessDenied)
+        }
+
         // send
         let serv = unsafe {
             let lck = mtx.lock();
@@ -392,91 +965,648 @@ impl Kern {
         inst.await
     }
 
-    pub fn run(self) -> Result<(), KernErr> {
-        let kern_mtx = Mutex::new(self);
+    // queue message if node is unreachable, fail if reply doesn't come in time
+    pub async fn send_remote(mtx: &Mutex<Self>, route: Vec<[u16; 8]>, serv: String, msg: Msg) -> ServResult {
+        let until = mtx.lock().drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))? + NET_REPLY_TIMEOUT;
+        Self::remote(mtx, route, serv, msg, true, until).await
+    }
 
-        let kern_loop = async || {
-            loop {
-                let mut runs = kern_mtx.lock().tasks_queue.clone().into_iter().map(|t| {
-                    let task = t.clone();
-                    let run = t.run(&kern_mtx);
-    
-                    (task, (run, false))
-                }).collect::<Vec<_>>();
-    
-                kern_mtx.lock().tasks_queue = Vec::new();
-    
-                // run tasks
-                for (task, _) in runs.iter() {
-                    kern_mtx.lock().tasks_running.push(task.clone());
+    // fail if node is unreachable or doesn't reply in time
+    pub async fn try_send_remote(mtx: &Mutex<Self>, addr: [u16; 8], serv: String, msg: Msg, timeout: Duration) -> ServResult {
+        let until = mtx.lock().drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))? + micros(timeout);
+        Self::remote(mtx, Vec::from([addr]), serv, msg, false, until).await
+    }
+
+    // first node receives message, others are relays to destination
+    async fn remote(mtx: &Mutex<Self>, mut route: Vec<[u16; 8]>, serv: String, msg: Msg, queue: bool, until: u128) -> ServResult {
+        if route.is_empty() {
+            return Err(KernErr::DrvErr(DrvErr::Net(NetErr::Unreachable)))
+        }
+
+        let id = {
+            let mut grd = mtx.lock();
+            grd.last_net_id += 1;
+
+            let id = grd.last_net_id;
+            let addr = route.remove(0);
+            let dat = grd.net_encode(NetFrame::Req{id, serv, route, msg})?;
+
+            grd.net_track(id, addr)?;
+
+            // handshake in progress is not failure, frame waits in queue
+            if let Err(e) = grd.drv.net.send(addr, &dat) {
+                if !queue && !matches!(e, NetErr::Pending) {
+                    grd.net_forget(id);
+                    return Err(KernErr::DrvErr(DrvErr::Net(e)))
                 }
-    
-                loop {
-                    for (task, (run, done)) in &mut runs {
-                        // check signals
-                        {
-                            let mut grd = kern_mtx.lock();
-    
-                            if let Some(sig) = grd.tasks_signals.iter().find(|(id, _)| *id == task.id).map(|(_, sig)| sig.clone()) {
-                                match sig {
-                                    TaskSig::Kill => {
-                                        writeln!(grd, "INFO vnix:kern: killed task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
-                                        grd.tasks_running.extract_if(|t| t.id == task.id).next();
-                                        grd.tasks_signals.extract_if(|(id, _)| *id == task.id).next();
-                                        *done = true
-                                    }
-                                }
-                            }
-                        }
-    
-                        if *done {
-                            continue;
-                        }
+                grd.net_enqueue(addr, dat, e)?;
+            }
+            id
+        };
 
-                        // run task
-                        kern_mtx.lock().curr_task_id = task.id;
+        // wait for reply
+        future::poll_fn(|cx| {
+            let mut grd = mtx.lock();
 
-                        if let Some(res) = future::poll_immediate(run).await {
-                            match &res {
-                                Ok(..) => (), // writeln!(kern_mtx.lock(), "DEBG vnix:kern: done task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?,
-                                Err(e) => {
-                                    writeln!(kern_mtx.lock(), "ERR vnix:{}#{}: {:?}", task.name, task.id, e).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
-                                }
-                            };
-    
-                            kern_mtx.lock().task_result.push((task.id, res));
-                            kern_mtx.lock().tasks_running.extract_if(|t| t.id == task.id).next();
-                            *done = true;
-                        }
-                    }
-    
-                    // run new tasks
-                    if !kern_mtx.lock().tasks_queue.is_empty() {
-                        let mut new_runs = kern_mtx.lock().tasks_queue.clone().into_iter().map(|t| {
-                            let task = t.clone();
-                            let run = t.run(&kern_mtx);
-    
-                            (task, (run, false))
-                        }).collect::<Vec<_>>();
-    
-                        kern_mtx.lock().tasks_queue = Vec::new();
-    
-                        for (task, _) in new_runs.iter() {
-                            kern_mtx.lock().tasks_running.push(task.clone());
-                            // writeln!(kern_mtx.lock(), "DEBG vnix:kern: run task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+            if let Some((_, _, res)) = grd.net_result.extract_if(|(i, _, _)| *i == id).next() {
+                return Poll::Ready(res)
+            }
+
+            // late reply is dropped, queued message is not sent anymore
+            match grd.drv.time.uptime(TimeUnit::Micro) {
+                Ok(now) if now < until => grd.wait(TaskWait::Time(until), cx.waker()),
+                Ok(..) => {
+                    grd.net_forget(id);
+
+                    if grd.net_queue.extract_if(|p| p.id == id).next().is_some() {
+                        if let Err(e) = grd.save_net_queue() {
+                            return Poll::Ready(Err(e))
                         }
-    
-                        runs.append(&mut new_runs);
                     }
-    
-                    // done
-                    if runs.iter().all(|(_, (_, done))| *done) {
-                        break;
+                    return Poll::Ready(Err(KernErr::DrvErr(DrvErr::Net(NetErr::Timeout))))
+                },
+                Err(e) => return Poll::Ready(Err(KernErr::DrvErr(DrvErr::Time(e))))
+            }
+
+            grd.wait(TaskWait::Net(id), cx.waker());
+            Poll::Pending
+        }).await
+    }
+
+    // sign frame by node key with fresh nonce
+    fn net_encode(&mut self, frame: NetFrame) -> Result<Vec<u8>, KernErr> {
+        let mut nonce = [0; 8];
+        self.drv.rnd.get_bytes(&mut nonce).map_err(|e| KernErr::DrvErr(DrvErr::Rnd(e)))?;
+
+        frame.encode(u64::from_le_bytes(nonce), self.get_node()?)
+    }
+
+    // send without waiting for reply
+    pub fn net_push(&mut self, addr: [u16; 8], serv: String, msg: Msg) -> Result<(), KernErr> {
+        self.last_net_id += 1;
+
+        let id = self.last_net_id;
+        let dat = self.net_encode(NetFrame::Req{id, serv, route: Vec::new(), msg})?;
+
+        self.net_track(id, addr)?;
+
+        if let Err(e) = self.drv.net.send(addr, &dat) {
+            self.net_enqueue(addr, dat, e)?;
+        }
+
+        self.net_detached.push(id);
+        Ok(())
+    }
+
+    // frame waiting for handshake is sent on next poll
+    fn net_enqueue(&mut self, addr: [u16; 8], frame: Vec<u8>, err: NetErr) -> Result<(), KernErr> {
+        let mut p = NetPending::new(addr, frame)?;
+        p.next = self.drv.time.uptime(TimeUnit::Milli).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+
+        if !matches!(err, NetErr::Pending) {
+            p.tries = 1;
+            p.next += p.backoff();
+        }
+
+        self.net_queue.push(p);
+        self.save_net_queue()
+    }
+
+    pub fn net_drop(&mut self, id: usize) -> Result<(), KernErr> {
+        self.net_queue.extract_if(|p| p.id == id).next().ok_or(KernErr::NetMsgNotFound)?;
+        self.net_forget(id);
+        self.net_done(id, Err(KernErr::NetMsgDropped))?;
+
+        self.save_net_queue()
+    }
+
+    // time of sending is kept to forget request, that gets no reply
+    fn net_track(&mut self, id: usize, addr: [u16; 8]) -> Result<(), KernErr> {
+        let now = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+        self.net_sent.push((id, addr, now));
+
+        Ok(())
+    }
+
+    fn net_forget(&mut self, id: usize) {
+        self.net_sent.retain(|(i, _, _)| *i != id);
+        self.net_relaying.retain(|(i, _, _)| *i != id);
+        self.net_detached.retain(|i| *i != id);
+    }
+
+    // keep reply only for waiting task, restored from disk or killed one waits for nothing
+    fn net_done(&mut self, id: usize, res: ServResult) -> Result<(), KernErr> {
+        if self.tasks_waiting.iter().any(|(w, _)| *w == TaskWait::Net(id)) {
+            let now = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+
+            self.net_result.push((id, now, res));
+            self.wake(|w| *w == TaskWait::Net(id));
+        }
+        Ok(())
+    }
+
+    // requests without reply in time and replies nobody took
+    fn net_expire(&mut self, now: u128) -> Result<(), KernErr> {
+        let queued = self.net_queue.iter().map(|p| p.id).collect::<Vec<_>>();
+        let expired = self.net_sent.iter()
+            .filter(|(id, _, t)| *t + NET_REPLY_TIMEOUT <= now && !queued.contains(id))
+            .map(|(id, _, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in expired {
+            self.net_forget(id);
+            self.net_done(id, Err(KernErr::DrvErr(DrvErr::Net(NetErr::Timeout))))?;
+        }
+
+        self.net_result.retain(|(_, t, _)| *t + NET_REPLY_TIMEOUT > now);
+        Ok(())
+    }
+
+    fn save_net_queue(&mut self) -> Result<(), KernErr> {
+        let dat = NetPending::encode_queue(&self.net_queue);
+        self.drv.disk.write(NET_QUEUE_NAME, &dat).map_err(|e| KernErr::DrvErr(DrvErr::Disk(e)))
+    }
+
+    fn net_retry(&mut self) -> Result<(), KernErr> {
+        if self.net_queue.is_empty() {
+            return Ok(())
+        }
+
+        let mcs = self.drv.time.uptime(TimeUnit::Micro).map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+        let now = mcs / 1000;
+
+        let (drv, sent) = (&mut self.drv, &mut self.net_sent);
+        let (mut tried, mut failed) = (false, Vec::new());
+
+        self.net_queue.retain_mut(|p| {
+            if p.next > now {
+                return true
+            }
+
+            // reply is waited from now
+            match drv.net.send(p.addr, &p.frame) {
+                Err(NetErr::Pending) => return true,
+                Ok(..) => {
+                    sent.iter_mut().filter(|(i, _, _)| *i == p.id).for_each(|(_, _, t)| *t = mcs);
+                    tried = true;
+                    return false
+                },
+                Err(..) => tried = true
+            }
+
+            p.tries += 1;
+            p.next = now + p.backoff();
+
+            if p.tries >= NET_RETRY_MAX {
+                failed.push(p.id);
+                return false
+            }
+            true
+        });
+
+        for id in failed {
+            self.net_forget(id);
+            self.net_done(id, Err(KernErr::DrvErr(DrvErr::Net(NetErr::Unreachable))))?;
+        }
+
+        // tries and delay survive reboot too
+        if tried {
+            self.save_net_queue()?;
+        }
+        Ok(())
+    }
+
+    // error of other node is passed as is
+    fn net_reply(&mut self, addr: [u16; 8], id: usize, res: ServResult) -> Result<(), KernErr> {
+        let res = res.map_err(|e| match e {
+            KernErr::RemoteFault(e) => e,
+            e => format!("{e:?}")
+        });
+        let dat = self.net_encode(NetFrame::Res{id, res})?;
+
+        self.drv.net.send(addr, &dat).map_err(|e| KernErr::DrvErr(DrvErr::Net(e)))
+    }
+
+    fn net_relay(&mut self, from: [u16; 8], from_id: usize, serv: String, mut route: Vec<[u16; 8]>, msg: Msg) -> Result<(), KernErr> {
+        let addr = route.remove(0);
+
+        // relay only to known node
+        if !self.peers.iter().any(|p| p.addr == addr) {
+            return Err(KernErr::NodeNotFound)
+        }
+
+        self.last_net_id += 1;
+
+        let id = self.last_net_id;
+        let dat = self.net_encode(NetFrame::Req{id, serv, route, msg})?;
+
+        if let Err(e) = self.drv.net.send(addr, &dat) {
+            self.net_enqueue(addr, dat, e)?;
+        }
+
+        self.net_track(id, addr)?;
+        self.net_relaying.push((id, from, from_id));
+        Ok(())
+    }
+
+    // reply of user not logged in on other node is signed by node itself
+    fn net_sign(&self, usr: Option<&str>, msg: Msg) -> Result<Msg, KernErr> {
+        if usr == Some(msg.ath.as_str()) {
+            return Ok(msg)
+        }
+
+        let node = self.get_node()?.clone();
+
+        match msg.to {
+            Some(to) => Msg::new_enc(node, &to, msg.msg),
+            None => Msg::new(node, msg.msg)
+        }
+    }
+
+    fn net_verify(&self, key: &str, msg: &Msg) -> Result<(), KernErr> {
+        let usr = if msg.ath == NODE_USR {
+            Usr::guest(NODE_USR, key, key)?
+        } else {
+            self.get_usr(&msg.ath)?
+        };
+        usr.verify(msg.body(), &msg.sign, &msg.hash)
+    }
+
+    fn net_serve(&mut self, addr: [u16; 8], id: usize, serv: String, msg: Msg) -> Result<(), KernErr> {
+        let peer = self.peers.iter().find(|p| p.addr == addr).cloned().ok_or(KernErr::NodeNotFound)?;
+
+        // storage replication is sent by node itself and reaches storage even if it is not exported
+        let by_node = msg.ath == NODE_USR && Usr::guest(NODE_USR, &peer.key, &peer.key)?.verify(msg.body(), &msg.sign, &msg.hash).is_ok();
+        let repl = serv == "io.store" && msg.msg.clone().as_map_find("repl").is_some();
+
+        if repl != by_node {
+            return Err(KernErr::ServAccessDenied)
+        }
+
+        // user logged in here with same keys runs request as itself, others run as node it came from
+        let usr = match self.get_usr(&msg.ath) {
+            Ok(usr) if usr.can_sign() && usr.verify(msg.body(), &msg.sign, &msg.hash).is_ok() => Some(msg.ath),
+            _ => None
+        };
+        let ath = usr.clone().unwrap_or(peer.name);
+
+        // advertise exported services only
+        if msg.to.is_none() && msg.msg.clone().as_str().map_or(false, |s| s.as_str() == "serv") {
+            let u = Unit::map(&[
+                (Unit::str("msg"), self.serv_list(true))
+            ]);
+            let res = self.msg(&ath, u).and_then(|msg| self.net_sign(usr.as_deref(), msg)).map(Some);
+            return self.net_reply(addr, id, res)
+        }
+
+        if !repl && !self.is_exported(&serv) {
+            return Err(KernErr::ServNotExported)
+        }
+
+        // encrypted message is passed to service as is
+        let u = self.new_unit(msg.msg);
+        let task_id = self.reg_task(&ath, "net.serv", TaskRun(u, serv))?;
+
+        if let Some(to) = msg.to {
+            if let Some(t) = self.tasks_queue.iter_mut().find(|t| t.id == task_id) {
+                t.to = Some(to);
+            }
+        }
+
+        self.net_serving.push((task_id, addr, id, usr));
+        Ok(())
+    }
+
+    fn net_recv(&mut self, addr: [u16; 8], dat: &[u8]) -> Result<(), KernErr> {
+        // signed by known node
+        let key = match self.peers.iter().find(|p| p.addr == addr) {
+            Some(peer) => peer.key.clone(),
+            None => return Ok(())
+        };
+
+        let (frame, nonce) = match NetFrame::open(dat, &key) {
+            Ok(frame) => frame,
+            Err(..) => return Ok(())
+        };
+
+        // replayed
+        if self.net_nonces.contains(&nonce) {
+            return Ok(())
+        }
+
+        self.net_nonces.push(nonce);
+
+        if self.net_nonces.len() > NET_NONCE_MAX {
+            self.net_nonces.remove(0);
+        }
+
+        match frame {
+            NetFrame::Req{id, serv, route, msg} => {
+                // relay signed message untouched, destination checks it
+                let res = if route.is_empty() {
+                    self.net_serve(addr, id, serv, msg)
+                } else {
+                    self.net_relay(addr, id, serv, route, msg)
+                };
+
+                if let Err(e) = res {
+                    self.net_reply(addr, id, Err(e))?
+                }
+            },
+            NetFrame::Res{id, res} => {
+                // reply for request sent to this node
+                if self.net_sent.extract_if(|(i, a, _)| *i == id && *a == addr).next().is_none() {
+                    return Ok(())
+                }
+
+                // frame is signed by node, reply is signed by user with same keys here or by that node
+                let res = match res {
+                    Ok(Some(msg)) => self.net_verify(&key, &msg).map(|_| Some(msg)),
+                    Ok(None) => Ok(None),
+                    Err(e) => Err(KernErr::RemoteFault(e))
+                };
+
+                // relay reply back, node of other side doesn't know signing node
+                let relay = self.net_relaying.extract_if(|(i, _, _)| *i == id).next();
+
+                if let Some((_, addr, id)) = relay {
+                    let res = match res {
+                        Ok(Some(msg)) if msg.ath == NODE_USR => self.net_sign(None, msg).map(Some),
+                        res => res
+                    };
+                    return self.net_reply(addr, id, res)
+                }
+
+                // nobody waits for reply
+                if self.net_detached.extract_if(|i| *i == id).next().is_some() {
+                    return Ok(())
+                }
+
+                self.net_done(id, res)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn net_poll(&mut self) -> Result<(), KernErr> {
+        // incoming frames, failed one does not stop others
+        while let Some((addr, dat)) = self.drv.net.recv().map_err(|e| KernErr::DrvErr(DrvErr::Net(e)))? {
+            if let Err(e) = self.net_recv(addr, &dat) {
+                writeln!(self, "ERR vnix:kern:net: {:?}", e).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+            }
+        }
+
+        // retry queued messages
+        self.net_retry()?;
+
+        // reply served requests
+        let done = self.net_serving.extract_if(|(task_id, _, _, _)| self.task_result.iter().any(|(id, _, _, _)| id == task_id)).collect::<Vec<_>>();
+
+        for (task_id, addr, id, usr) in done {
+            let res = match self.get_task_result(task_id).unwrap_or(Ok(None)) {
+                Ok(Some(msg)) => self.net_sign(usr.as_deref(), msg).map(Some),
+                res => res
+            };
+
+            if let Err(e) = self.net_reply(addr, id, res) {
+                writeln!(self, "ERR vnix:kern:net: {:?}", e).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run(self) -> Result<(), KernErr> {
+        let kern_mtx = Mutex::new(self);
+        let mut sched = Sched::new(&kern_mtx);
+
+        loop {
+            sched.step()?;
+        }
+    }
+}
+
+// polls tasks of kernel, one step runs every woken task once
+pub struct Sched<'a> {
+    kern: &'a Mutex<Kern>,
+    runs: Vec<(Task, TaskRunAsync<'a>, Arc<TaskWake>)>
+}
+
+impl<'a> Sched<'a> {
+    pub fn new(kern: &'a Mutex<Kern>) -> Self {
+        Sched {kern, runs: Vec::new()}
+    }
+
+    pub fn step(&mut self) -> Result<(), KernErr> {
+        // run new tasks
+        let queue = core::mem::take(&mut self.kern.lock().tasks_queue);
+
+        for t in queue {
+            self.kern.lock().tasks_running.push(t.clone());
+            // writeln!(self.kern.lock(), "DEBG vnix:kern: run task `{}#{}`", t.name, t.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+
+            let run = t.clone().run(self.kern);
+            self.runs.push((t, run, TaskWake::new()));
+        }
+
+        // network
+        let res = self.kern.lock().net_poll();
+
+        if let Err(e) = res {
+            writeln!(self.kern.lock(), "ERR vnix:kern:net: {:?}", e).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+        }
+
+        // timers and input
+        self.kern.lock().wake_timers()?;
+
+        // higher priority first
+        {
+            let grd = self.kern.lock();
+
+            for (task, _, _) in self.runs.iter_mut() {
+                if let Some(t) = grd.tasks_running.iter().find(|t| t.id == task.id) {
+                    task.prio = t.prio;
+                }
+            }
+            self.runs.sort_by(|(a, _, _), (b, _, _)| b.prio.cmp(&a.prio));
+        }
+
+        let mut done = Vec::new();
+
+        for (task, run, wake) in self.runs.iter_mut() {
+            // check signals, user signals are left for task
+            {
+                let mut grd = self.kern.lock();
+                let sigs = grd.tasks_signals.extract_if(|(id, sig)| *id == task.id && !matches!(sig, TaskSig::Usr(..))).map(|(_, sig)| sig).collect::<Vec<_>>();
+
+                for sig in sigs {
+                    match sig {
+                        TaskSig::Kill => {
+                            writeln!(grd, "INFO vnix:kern: killed task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+                            grd.task_kill(task.id)?;
+                            done.push(task.id);
+                            break;
+                        },
+                        TaskSig::Stop => {
+                            if !grd.tasks_stopped.contains(&task.id) {
+                                writeln!(grd, "INFO vnix:kern: stopped task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+                                grd.tasks_stopped.push(task.id);
+                            }
+                        },
+                        TaskSig::Cont => {
+                            if grd.tasks_stopped.contains(&task.id) {
+                                writeln!(grd, "INFO vnix:kern: continued task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+                                grd.tasks_stopped.retain(|id| *id != task.id);
+                            }
+                        },
+                        TaskSig::Usr(..) => ()
                     }
                 }
+
+                // stopped task keeps its state and wakes, but is not polled
+                if done.contains(&task.id) || grd.tasks_stopped.contains(&task.id) {
+                    continue;
+                }
             }
-        };
 
-        block_on(kern_loop())
+            // blocked task is not polled until woken
+            if !wake.take() {
+                continue;
+            }
+
+            // run task
+            {
+                let mut grd = self.kern.lock();
+                grd.curr_task_id = Some(task.id);
+                grd.budget = task.prio.budget();
+            }
+
+            let waker = waker(wake.clone());
+            let mut cx = Context::from_waker(&waker);
+
+            let poll = run.as_mut().poll(&mut cx);
+            self.kern.lock().curr_task_id = None;
+
+            if let Poll::Ready(res) = poll {
+                match &res {
+                    Ok(..) => (), // writeln!(self.kern.lock(), "DEBG vnix:kern: done task `{}#{}`", task.name, task.id).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?,
+                    Err(e) => {
+                        writeln!(self.kern.lock(), "ERR vnix:{}#{}: {:?}", task.name, task.id, e).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+                    }
+                };
+
+                let mut grd = self.kern.lock();
+                let id = task.id;
+
+                grd.tasks_running.extract_if(|t| t.id == id).next();
+                grd.task_done(id, task.parent_id, res)?;
+
+                done.push(id);
+            }
+        }
+
+        // forget done tasks with their wakers and signals
+        for (task, _, wake) in self.runs.extract_if(|(task, _, _)| done.contains(&task.id)) {
+            let waker = waker(wake);
+            let mut grd = self.kern.lock();
+
+            grd.tasks_waiting.retain(|(_, w)| !w.will_wake(&waker));
+            grd.tasks_signals.retain(|(id, _)| *id != task.id);
+            grd.tasks_stopped.retain(|id| *id != task.id);
+            grd.tasks_deadline.retain(|(id, _)| *id != task.id);
+            grd.tasks_timedout.retain(|id| *id != task.id);
+        }
+
+        // nothing to run
+        let mut grd = self.kern.lock();
+
+        if grd.tasks_queue.is_empty() && !self.runs.iter().any(|(task, _, wake)| wake.is_woken() && !grd.tasks_stopped.contains(&task.id)) {
+            grd.idle()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    use spin::Mutex;
+
+    use crate::vnix::harness::{node, cluster};
+
+    use super::{KernErr, Sched, TaskRun, TaskSig, TimeUnit, Duration, Unit, UnitNew};
+
+    #[test]
+    fn reply_signed_by_user_or_node() {
+        let [node1, mut node2] = cluster().map(Mutex::into_inner);
+        let key = node2.get_node().unwrap().pub_key.clone();
+
+        // proxy user of other node has own keys, node signs for it
+        let msg = node2.msg("node1", Unit::uint(1)).unwrap();
+        assert!(matches!(node1.net_verify(&key, &msg), Err(KernErr::UsrNotFound)));
+
+        let mut msg = node2.net_sign(None, msg).unwrap();
+        node1.net_verify(&key, &msg).unwrap();
+
+        msg.msg = Unit::uint(2);
+        assert!(matches!(node1.net_verify(&key, &msg), Err(KernErr::HashVerifyFault)));
+    }
+
+    #[test]
+    fn kill_queued_subtree() {
+        let mut kern = node(1, Arc::default());
+        let run = || TaskRun(Unit::none(), "math.calc".into());
+
+        // parent -> child -> grandchild, none of them started
+        let parent = kern.reg_task("super", "parent", run()).unwrap();
+        kern.curr_task_id = Some(parent);
+        let child = kern.reg_task("super", "child", run()).unwrap();
+        kern.curr_task_id = Some(child);
+        let grandchild = kern.reg_task("super", "grandchild", run()).unwrap();
+        kern.curr_task_id = None;
+
+        kern.task_sig(parent, TaskSig::Kill).unwrap();
+        assert!(kern.tasks_queue.is_empty());
+
+        for id in [parent, child, grandchild] {
+            assert!(matches!(kern.get_task_result(id), Some(Err(KernErr::TaskKilled))));
+        }
+    }
+
+    #[test]
+    fn result_stamped_by_uptime() {
+        let kern = Mutex::new(node(1, Arc::default()));
+        let mut sched = Sched::new(&kern);
+
+        for _ in 0..10 {
+            sched.step().unwrap();
+        }
+
+        // time passes between ticks
+        let mut grd = kern.lock();
+        grd.drv.time.wait(Duration::Micro(2500)).unwrap();
+
+        let id = grd.reg_task("super", "test", TaskRun(Unit::none(), "math.calc".into())).unwrap();
+        grd.task_sig(id, TaskSig::Kill).unwrap();
+
+        let now = grd.drv.time.uptime(TimeUnit::Micro).unwrap();
+        assert!(grd.task_result.iter().any(|(i, _, t, _)| *i == id && *t == now));
+    }
+
+    #[test]
+    fn stop_queued_task() {
+        let mut kern = node(1, Arc::default());
+        let id = kern.reg_task("super", "test", TaskRun(Unit::none(), "math.calc".into())).unwrap();
+
+        kern.task_sig(id, TaskSig::Stop).unwrap();
+        assert!(kern.is_task_stopped(id));
+
+        kern.task_sig(id, TaskSig::Cont).unwrap();
+        assert!(!kern.is_task_stopped(id));
+
+        assert!(matches!(kern.task_sig(id + 1, TaskSig::Kill), Err(KernErr::TaskNotFound)));
+        assert!(matches!(kern.task_timeout(id + 1, Duration::Milli(10)), Err(KernErr::TaskNotFound)));
+        assert_eq!(kern.tasks_queue.iter().map(|t| t.id).collect::<Vec<_>>(), [id]);
     }
 }
@@ -1,7 +1,10 @@
 pub mod unit;
 pub mod msg;
+pub mod wire;
 pub mod user;
 pub mod serv;
 pub mod task;
 pub mod kern;
+pub mod policy;
+pub mod peer;
 pub mod driver;
\ No newline at end of file
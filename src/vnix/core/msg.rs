@@ -8,7 +8,7 @@ use base64ct::{Base64, Encoding};
 use crate::vnix::core::driver::MemSizeUnits;
 
 use super::kern::KernErr;
-use super::unit::{Unit, UnitAsBytes};
+use super::unit::{Unit, UnitNew, UnitAsBytes};
 use super::user::Usr;
 
 
@@ -16,6 +16,7 @@ use super::user::Usr;
 pub struct Msg {
     pub msg: Unit,
     pub ath: String,
+    pub to: Option<String>, // recipient, if `msg` is encrypted
     pub hash: String,
     pub sign: String,
     pub size: usize
@@ -23,25 +24,54 @@ pub struct Msg {
 
 impl Display for Msg {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if let Some(to) = &self.to {
+            return write!(f, "{{ath:{} to:{} size:{} msg:{} hash:`{}` sign:`{}`}}", self.ath, to, self.size, self.msg, self.hash, self.sign)
+        }
         write!(f, "{{ath:{} size:{} msg:{} hash:`{}` sign:`{}`}}", self.ath, self.size, self.msg, self.hash, self.sign)
     }
 }
 
 impl Msg {
     pub fn new(usr: Usr, msg: Unit) -> Result<Self, KernErr> {
-        let h = Sha3_256::digest(msg.clone().as_bytes());
+        Self::signed(usr, msg, None)
+    }
+
+    // signature covers recipient too, so message cannot be readdressed
+    pub fn new_enc(usr: Usr, to: &str, enc: Unit) -> Result<Self, KernErr> {
+        Self::signed(usr, enc, Some(to.into()))
+    }
+
+    fn signed(usr: Usr, msg: Unit, to: Option<String>) -> Result<Self, KernErr> {
+        let body = Self::body_of(&msg, to.as_deref());
+        let h = Sha3_256::digest(body.clone().as_bytes());
 
         let hash = Base64::encode_string(&h[..]);
-        let sign = usr.sign(msg.clone())?;
+        let sign = usr.sign(body)?;
 
         let size = msg.size(MemSizeUnits::Bytes);
 
         Ok(Msg {
             ath: usr.name,
+            to,
             msg,
             hash: hash.into(),
             sign,
             size
         })
     }
+
+    fn body_of(msg: &Unit, to: Option<&str>) -> Unit {
+        match to {
+            Some(to) => Unit::map(&[
+                (Unit::str("enc"), msg.clone()),
+                (Unit::str("to"), Unit::str(to))
+            ]),
+            None => msg.clone()
+        }
+    }
+
+    // unit covered by signature
+    pub fn body(&self) -> Unit {
+        Self::body_of(&self.msg, self.to.as_deref())
+    }
 }
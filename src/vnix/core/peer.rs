@@ -0,0 +1,67 @@
+use alloc::vec::Vec;
+use alloc::string::{String, ToString};
+
+use crate::vnix::utils::RamStore;
+
+use super::kern::Addr;
+use super::unit::{Unit, UnitNew, UnitAs};
+
+
+pub const NET_PATH: [&'static str; 2] = ["sys", "net"];
+pub const PEERS_PATH: [&'static str; 3] = ["sys", "net", "peers"];
+pub const HIDDEN_PATH: [&'static str; 3] = ["sys", "net", "hide"];
+pub const EXPORTED_PATH: [&'static str; 3] = ["sys", "net", "exp"];
+
+// services private to node unless exported explicitly
+pub const PRIVATE_SERV: [&'static str; 2] = ["sys.*", "io.store"];
+
+// node key pair signs frames, other nodes know its public key as `key` of peer
+pub const NODE_KEY_NAME: &'static str = "vnix.node";
+pub const NODE_USR: &'static str = "node";
+
+#[derive(Debug, Clone)]
+pub struct Peer {
+    pub name: String,
+    pub addr: [u16; 8],
+    pub key: String
+}
+
+// `0x..:0x..:..`, same as in stream address
+pub fn parse_remote_addr(s: &str) -> Option<[u16; 8]> {
+    let addr = s.split(':').map(|s| u16::from_str_radix(s.strip_prefix("0x")?, 16).ok()).collect::<Option<Vec<_>>>()?;
+    addr.try_into().ok()
+}
+
+impl Peer {
+    // name must not be confused with `loc`, remote address or local user, peer is registered as user
+    pub fn valid_name(name: &str) -> bool {
+        !name.is_empty() && name != "loc" && name != RamStore::SUPER && name != NODE_USR && !name.starts_with("0x") && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    }
+
+    pub fn from_unit(u: Unit) -> Option<Self> {
+        let name = u.clone().as_map_find("name")?.as_str()?.to_string();
+        let addr = parse_remote_addr(&u.clone().as_map_find("addr")?.as_str()?)?;
+        let key = u.as_map_find("key")?.as_str()?.to_string();
+
+        if !Self::valid_name(&name) {
+            return None
+        }
+        Some(Peer {name, addr, key})
+    }
+
+    pub fn as_unit(&self) -> Unit {
+        Unit::map(&[
+            (Unit::str("name"), Unit::str(&self.name)),
+            (Unit::str("addr"), Unit::str(&Addr::Remote(self.addr).to_string())),
+            (Unit::str("key"), Unit::str(&self.key))
+        ])
+    }
+
+    pub fn list_from_unit(u: Unit) -> Option<Vec<Self>> {
+        u.as_list()?.iter().map(|u| Self::from_unit(u.clone())).collect()
+    }
+
+    pub fn list_as_unit(peers: &[Peer]) -> Unit {
+        Unit::list(&peers.iter().map(|p| p.as_unit()).collect::<Vec<_>>())
+    }
+}
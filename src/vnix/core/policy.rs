@@ -0,0 +1,256 @@
+use alloc::vec::Vec;
+use alloc::string::{String, ToString};
+
+use super::unit::{Unit, UnitNew, UnitAs};
+
+
+pub const POLICY_PATH: [&'static str; 2] = ["sys", "pol"];
+
+#[derive(Debug, Clone)]
+pub struct PolicyRule {
+    pub com: String,
+    pub allow: Option<Vec<String>>,
+    pub deny: Vec<String>
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    pub grp: Vec<(String, Vec<String>)>,
+    pub serv: Vec<(String, Vec<PolicyRule>)>
+}
+
+impl PolicyRule {
+    pub fn from_unit(u: Unit) -> Option<Self> {
+        let names = |u: Unit| u.as_list()?.iter().map(|u| u.clone().as_str().map(|s| s.to_string())).collect::<Option<Vec<_>>>();
+
+        let allow = match u.clone().as_map_find("allow") {
+            Some(u) => Some(names(u)?),
+            None => None
+        };
+
+        let deny = match u.clone().as_map_find("deny") {
+            Some(u) => names(u)?,
+            None => Vec::new()
+        };
+
+        Some(PolicyRule {
+            com: u.as_map_find("com")?.as_str()?.to_string(),
+            allow,
+            deny
+        })
+    }
+
+    pub fn as_unit(&self) -> Unit {
+        let names = |lst: &Vec<String>| Unit::list(&lst.iter().map(|s| Unit::str(s)).collect::<Vec<_>>());
+        let mut map = Vec::from([(Unit::str("com"), Unit::str(&self.com))]);
+
+        if let Some(allow) = &self.allow {
+            map.push((Unit::str("allow"), names(allow)));
+        }
+
+        if !self.deny.is_empty() {
+            map.push((Unit::str("deny"), names(&self.deny)));
+        }
+        Unit::map(&map)
+    }
+
+    // `*`, `set.*` or exact command
+    fn matches(&self, com: &str) -> bool {
+        if self.com == "*" {
+            return true
+        }
+
+        if let Some(pref) = self.com.strip_suffix(".*") {
+            return com == pref || com.strip_prefix(pref).map_or(false, |s| s.starts_with("."))
+        }
+        self.com == com
+    }
+}
+
+impl Policy {
+    pub fn rules_from_unit(u: Unit) -> Option<Vec<PolicyRule>> {
+        u.as_list()?.iter().map(|u| PolicyRule::from_unit(u.clone())).collect()
+    }
+
+    pub fn rules_as_unit(rules: &[PolicyRule]) -> Unit {
+        Unit::list(&rules.iter().map(|r| r.as_unit()).collect::<Vec<_>>())
+    }
+
+    pub fn from_unit(u: Unit) -> Option<Self> {
+        let grp = match u.clone().as_map_find("grp") {
+            Some(grp) => grp.as_map()?.iter().map(|(name, usrs)| {
+                let name = name.clone().as_str()?.to_string();
+                let usrs = usrs.clone().as_list()?.iter().map(|u| u.clone().as_str().map(|s| s.to_string())).collect::<Option<Vec<_>>>()?;
+                Some((name, usrs))
+            }).collect::<Option<Vec<_>>>()?,
+            None => Vec::new()
+        };
+
+        let serv = match u.as_map_find("serv") {
+            Some(serv) => serv.as_map()?.iter().map(|(name, rules)| {
+                Some((name.clone().as_str()?.to_string(), Self::rules_from_unit(rules.clone())?))
+            }).collect::<Option<Vec<_>>>()?,
+            None => Vec::new()
+        };
+
+        Some(Policy {grp, serv})
+    }
+
+    pub fn as_unit(&self) -> Unit {
+        let grp = self.grp.iter().map(|(name, usrs)| {
+            (Unit::str(name), Unit::list(&usrs.iter().map(|s| Unit::str(s)).collect::<Vec<_>>()))
+        }).collect::<Vec<_>>();
+
+        let serv = self.serv.iter().map(|(name, rules)| {
+            (Unit::str(name), Self::rules_as_unit(rules))
+        }).collect::<Vec<_>>();
+
+        Unit::map(&[
+            (Unit::str("grp"), Unit::map(&grp)),
+            (Unit::str("serv"), Unit::map(&serv))
+        ])
+    }
+
+    pub fn set_rules(&mut self, serv: &str, rules: Vec<PolicyRule>) {
+        self.serv.retain(|(name, _)| name != serv);
+
+        if !rules.is_empty() {
+            self.serv.push((serv.into(), rules));
+        }
+    }
+
+    pub fn get_rules(&self, serv: &str) -> Option<&Vec<PolicyRule>> {
+        self.serv.iter().find(|(name, _)| name == serv).map(|(_, rules)| rules)
+    }
+
+    // user name, group name or `any`
+    fn is_member(&self, ath: &str, subj: &str) -> bool {
+        subj == "any" || subj == ath || self.grp.iter().any(|(name, usrs)| name == subj && usrs.iter().any(|u| u == ath))
+    }
+
+    fn permits(&self, ath: &str, rule: &PolicyRule) -> bool {
+        if rule.deny.iter().any(|s| self.is_member(ath, s)) {
+            return false
+        }
+        rule.allow.as_ref().map_or(true, |allow| allow.iter().any(|s| self.is_member(ath, s)))
+    }
+
+    // `com`, `(com ..)` or `{com:.. ..}`, none if command is known only after reading message
+    fn commands(msg: &Unit) -> Option<Vec<String>> {
+        if let Some(s) = msg.clone().as_str() {
+            return Some(Vec::from([s.to_string()]))
+        }
+
+        if let Some((s, _)) = msg.clone().as_pair() {
+            return s.as_str().map(|s| Vec::from([s.to_string()]))
+        }
+
+        if let Some(map) = msg.clone().as_map() {
+            return map.iter().map(|(k, _)| k.clone().as_str().map(|s| s.to_string())).collect()
+        }
+        None
+    }
+
+    // encrypted message is checked with `None`
+    pub fn check(&self, ath: &str, serv: &str, msg: Option<&Unit>) -> bool {
+        let rules = match self.get_rules(serv) {
+            Some(rules) => rules,
+            None => return true
+        };
+
+        let mut coms = match msg.and_then(|msg| Self::commands(msg)) {
+            Some(coms) => coms,
+            // any command could be sent, so every rule must permit it
+            None => return rules.iter().all(|r| self.permits(ath, r))
+        };
+
+        if coms.is_empty() {
+            coms.push(String::new());
+        }
+
+        // first matched rule decides
+        coms.iter().all(|com| {
+            match rules.iter().find(|r| r.matches(com)) {
+                Some(r) => self.permits(ath, r),
+                None => true
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Policy;
+    use super::super::kern::KernErr;
+    use super::super::unit::{Unit, UnitNew, UnitParse};
+
+    use crate::vnix::harness::{cluster, Nodes, res_str};
+
+    fn policy(s: &str) -> Policy {
+        Policy::from_unit(Unit::parse(s.chars()).unwrap().0).unwrap()
+    }
+
+    fn msg(s: &str) -> Unit {
+        Unit::parse(s.chars()).unwrap().0
+    }
+
+    #[test]
+    fn prefix_matches_whole_parts() {
+        let pol = policy("{serv:{io.store:[{com:`set.*` deny:[any]}]}}");
+
+        for com in ["set", "(set.mode (prv @a))", "(set.own.x 1)"] {
+            assert!(!pol.check("alice", "io.store", Some(&msg(com))), "{com} is not denied");
+        }
+
+        for com in ["setx", "(set_mode 1)", "(get.acl @a)"] {
+            assert!(pol.check("alice", "io.store", Some(&msg(com))), "{com} is denied");
+        }
+
+        // other service
+        assert!(pol.check("alice", "io.term", Some(&msg("set"))));
+    }
+
+    #[test]
+    fn overlapping_prefixes() {
+        // first matched rule decides, narrower one goes first
+        let pol = policy("{
+            grp:{adm:[alice bob]}
+            serv:{io.store:[
+                {com:set.mode allow:[adm] deny:[bob]}
+                {com:`set.*` deny:[any]}
+                {com:`*` allow:[any]}
+            ]}
+        }");
+
+        let mode = msg("(set.mode (prv @a))");
+        let own = msg("(set.own (bob @a))");
+
+        assert!(pol.check("alice", "io.store", Some(&mode)));
+        assert!(!pol.check("alice", "io.store", Some(&own)));
+
+        // deny wins over allow of same rule
+        assert!(!pol.check("bob", "io.store", Some(&mode)));
+        assert!(!pol.check("eve", "io.store", Some(&mode)));
+
+        assert!(pol.check("eve", "io.store", Some(&msg("(load @a)"))));
+
+        // every key of map is checked
+        assert!(!pol.check("alice", "io.store", Some(&msg("{load:@a set.own:eve}"))));
+
+        // encrypted message needs every rule to permit
+        assert!(!pol.check("alice", "io.store", None));
+        assert!(policy("{serv:{io.store:[{com:`set.*` allow:[alice]}]}}").check("alice", "io.store", None));
+    }
+
+    #[test]
+    fn super_bypasses_policy() {
+        let kerns = cluster();
+        let [node1, _] = &kerns;
+        node1.lock().policy = policy("{serv:{math.calc:[{com:`*` deny:[any]}]}}");
+
+        let mut nodes = Nodes::new(&kerns);
+
+        assert!(matches!(nodes.run(node1, "node2", "(neg 1)", "math.calc"), Err(KernErr::ServAccessDenied)));
+        assert_eq!(res_str(nodes.run(node1, "super", "(neg 1)", "math.calc")), "-1");
+    }
+}
@@ -1,8 +1,11 @@
 use core::pin::Pin;
 use core::future::Future;
 
-use futures::task::{Context, Poll};
+use core::sync::atomic::{AtomicBool, Ordering};
 
+use futures::task::{Context, Poll, ArcWake};
+
+use alloc::sync::Arc;
 use alloc::boxed::Box;
 use alloc::string::String;
 use spin::Mutex;
@@ -21,6 +24,18 @@ pub struct Yield {
     done: bool
 }
 
+// set by waker, kernel polls task only when it is set
+pub struct TaskWake(AtomicBool);
+
+// event, that blocked task waits for
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskWait {
+    Task(usize),
+    Net(usize),
+    Time(u128),
+    Input
+}
+
 impl Yield {
     pub fn now() -> Self {
         Self {done: false}
@@ -41,14 +56,8 @@ macro_rules! thread {
 macro_rules! task_result {
     ($id:expr, $kern:expr) => {
         {
-            use crate::vnix::core::task;
-            let res = loop {
-                if let Some(res) = $kern.lock().get_task_result($id) {
-                    break res;
-                }
-                task::Yield::now().await;
-            };
-            res
+            use crate::vnix::core::kern::Kern;
+            Kern::task_wait($kern, $id).await
         }
     };
 }
@@ -67,31 +76,98 @@ impl Future for Yield {
     }
 }
 
+impl TaskWake {
+    pub fn new() -> Arc<Self> {
+        Arc::new(TaskWake(AtomicBool::new(true)))
+    }
+
+    pub fn is_woken(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::AcqRel)
+    }
+}
+
+impl ArcWake for TaskWake {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::Release);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskRun(pub Unit, pub String);
 
+// higher priority task is polled first and runs longer before forced to yield
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaskPrio {
+    Low,
+    Norm,
+    High
+}
+
 #[derive(Debug, Clone)]
 pub struct Task {
     pub usr: String,
     pub name: String,
     pub id: usize,
     pub parent_id: usize,
-    pub run: TaskRun
+    pub prio: TaskPrio,
+    pub run: TaskRun,
+    pub to: Option<String> // recipient, if unit of `run` is encrypted
 }
 
 #[derive(Debug, Clone)]
 pub enum TaskSig {
-    Kill
+    Kill,
+    Stop,
+    Cont,
+    Usr(String)
+}
+
+impl TaskPrio {
+    // unit reads per poll
+    pub fn budget(&self) -> usize {
+        match self {
+            TaskPrio::Low => 32,
+            TaskPrio::Norm => 128,
+            TaskPrio::High => 512
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(TaskPrio::Low),
+            "norm" => Some(TaskPrio::Norm),
+            "high" => Some(TaskPrio::High),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskPrio::Low => "low",
+            TaskPrio::Norm => "norm",
+            TaskPrio::High => "high"
+        }
+    }
 }
 
 impl Task {
-    pub fn new(usr: String, name: String, id: usize, parent_id: usize, run: TaskRun) -> Self {
-        Task{usr, name, id, parent_id, run}
+    pub fn new(usr: String, name: String, id: usize, parent_id: usize, prio: TaskPrio, run: TaskRun) -> Self {
+        Task{usr, name, id, parent_id, prio, run, to: None}
     }
 
     pub fn run(self, kern: &Mutex<Kern>) -> TaskRunAsync {
         thread!({
-            let msg = kern.lock().msg(&self.usr, self.run.0)?;
+            let msg = match &self.to {
+                Some(to) => {
+                    let usr = kern.lock().get_usr(&self.usr)?;
+                    Msg::new_enc(usr, to, self.run.0)?
+                },
+                None => kern.lock().msg(&self.usr, self.run.0)?
+            };
             Kern::send(kern, self.run.1, msg).await
         })
     }
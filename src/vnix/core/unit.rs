@@ -23,6 +23,7 @@ use crate::vnix::core::driver::MemSizeUnits;
 use crate::{maybe, maybe_ok, task_result};
 
 use super::kern::{Addr, KernErr, Kern};
+use super::peer::Peer;
 
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
@@ -90,7 +91,11 @@ pub enum UnitBin {
     PairUint8Uint24,
     PairUint16Uint24,
     PairUint24Uint24,
-    PairUint16Int32
+    PairUint16Int32,
+
+    // Network
+    AddrNode,
+    AddrRoute
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -260,6 +265,7 @@ macro_rules! as_map_find_as_async {
 pub trait UnitModify {
     fn find<'a, I>(&self, path: I) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone;
     fn replace<'a, I>(self, path: I, what: Unit) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone;
+    fn remove<'a, I>(self, path: I) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone;
 
     fn merge_with(self, what: Unit) -> Unit;
     fn merge<'a, I>(self, path: I, what: Unit) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone;
@@ -471,9 +477,25 @@ impl UnitAs for Unit {
 #[async_trait(?Send)]
 impl UnitReadAsyncI for Unit {
     async fn read_async(self, ath: Rc<String>, orig: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        // long reads are forced to yield
+        Kern::coop(kern).await;
+
         match self.0.as_ref() {
             UnitBase::Ref(path) => Ok(orig.find(path.iter().map(|s| s.as_str())).map(|u| (u, ath))),
-            UnitBase::Stream(msg, serv, _addr) => {
+            UnitBase::Stream(msg, serv, addr) => {
+                // remote or through relays
+                let route = kern.lock().resolve(addr)?;
+
+                if !route.is_empty() {
+                    let _msg = kern.lock().msg(&ath, msg.clone())?;
+
+                    let res = maybe!(Kern::send_remote(kern, route, Rc::unwrap_or_clone(serv.clone()), _msg).await);
+                    let msg = maybe_ok!(res.msg.as_map_find("msg"));
+
+                    // reply is signed by user of other node, result belongs to reader
+                    return Ok(Some((msg, ath)))
+                }
+
                 let run = TaskRun(msg.clone(), Rc::unwrap_or_clone(serv.clone()));
                 let id = kern.lock().reg_task(&ath, "unit.read", run)?;
 
@@ -494,6 +516,125 @@ impl UnitReadAsyncI for Unit {
     }
 }
 
+// `loc`, `node` or `0x..:..:0x..`
+fn parse_hop<I>(mut it: I) -> Result<(Addr, I), UnitParseErr> where I: Iterator<Item = char> + Clone {
+    let mut addr = [0; 8];
+
+    for i in 0..8 {
+        if i > 0 {
+            it = Unit::parse_ch(':', it)?;
+        }
+
+        let (s, tmp) = Unit::parse_str(it)?;
+        let s = s.as_str().ok_or(UnitParseErr::InvalidAddr)?;
+
+        if i == 0 && s.as_str() == "loc" {
+            return Ok((Addr::Local, tmp))
+        }
+
+        // node name from `sys.net` registry
+        if i == 0 && Peer::valid_name(&s) {
+            return Ok((Addr::Node(s.to_string()), tmp))
+        }
+
+        let hex = s.strip_prefix("0x").ok_or(UnitParseErr::InvalidAddr)?;
+        addr[i] = u16::from_str_radix(hex, 16).map_err(|_| UnitParseErr::InvalidAddr)?;
+
+        it = tmp;
+    }
+    Ok((Addr::Remote(addr), it))
+}
+
+// `:hop` or route through relays `:hop>hop>..`
+fn parse_addr<I>(it: I) -> Result<(Addr, I), UnitParseErr> where I: Iterator<Item = char> + Clone {
+    let it = Unit::parse_ch(':', it)?;
+    let (addr, mut it) = parse_hop(it)?;
+
+    let mut route = Vec::from([addr.clone()]);
+
+    while let Ok(tmp) = Unit::parse_ch('>', it.clone()) {
+        let (hop, tmp) = parse_hop(tmp)?;
+
+        // length of route is one byte in binary form
+        if route.len() >= u8::MAX as usize {
+            return Err(UnitParseErr::InvalidAddr)
+        }
+
+        route.push(hop);
+        it = tmp;
+    }
+
+    if route.len() > 1 {
+        return Ok((Addr::Route(route), it))
+    }
+    Ok((addr, it))
+}
+
+fn addr_as_bytes(addr: &Addr) -> Vec<u8> {
+    match addr {
+        Addr::Local => vec![UnitBin::AddrLoc as u8],
+        Addr::Remote(addr) => [UnitBin::AddrRemote as u8].into_iter().chain(addr.into_iter().flat_map(|e| e.to_le_bytes())).collect::<Vec<u8>>(),
+        Addr::Node(name) => [UnitBin::AddrNode as u8].into_iter()
+            .chain((name.len() as u32).to_le_bytes())
+            .chain(name.as_bytes().into_iter().cloned())
+            .collect::<Vec<u8>>(),
+        Addr::Route(route) => [UnitBin::AddrRoute as u8, route.len() as u8].into_iter()
+            .chain(route.iter().flat_map(|hop| addr_as_bytes(hop)))
+            .collect::<Vec<u8>>()
+    }
+}
+
+fn parse_addr_bytes<'a>(mut it: Iter<'a, u8>) -> Result<(Addr, Iter<'a, u8>), UnitParseErr> {
+    let addr = match *it.next().ok_or(UnitParseErr::UnexpectedEnd)? {
+        _b if _b == UnitBin::AddrLoc as u8 => Addr::Local,
+        _b if _b == UnitBin::AddrRemote as u8 => {
+            let addr = (0..8).map(|_| {
+                let bytes = [
+                    *it.next()?,
+                    *it.next()?
+                ];
+                Some(<u16>::from_le_bytes(bytes))
+            }).try_collect::<Vec<_>>()
+                .ok_or(UnitParseErr::UnexpectedEnd)?
+                .try_into()
+                .map_err(|_| UnitParseErr::UnexpectedEnd)?;
+
+            Addr::Remote(addr)
+        },
+        _b if _b == UnitBin::AddrNode as u8 => {
+            let bytes = [
+                *it.next().ok_or(UnitParseErr::UnexpectedEnd)?,
+                *it.next().ok_or(UnitParseErr::UnexpectedEnd)?,
+                *it.next().ok_or(UnitParseErr::UnexpectedEnd)?,
+                *it.next().ok_or(UnitParseErr::UnexpectedEnd)?
+            ];
+            let len = <u32>::from_le_bytes(bytes);
+
+            let bytes = (0..len).map(|_| it.next().map(|v| *v)).try_collect::<Vec<_>>().ok_or(UnitParseErr::UnexpectedEnd)?;
+            Addr::Node(String::from_utf8(bytes).map_err(|_| UnitParseErr::InvalidAddr)?)
+        },
+        _b if _b == UnitBin::AddrRoute as u8 => {
+            let len = *it.next().ok_or(UnitParseErr::UnexpectedEnd)?;
+            let mut route = Vec::new();
+
+            for _ in 0..len {
+                let (hop, tmp) = parse_addr_bytes(it)?;
+
+                // no nested routes
+                if let Addr::Route(..) = hop {
+                    return Err(UnitParseErr::InvalidAddr)
+                }
+
+                route.push(hop);
+                it = tmp;
+            }
+            Addr::Route(route)
+        },
+        _ => return Err(UnitParseErr::InvalidAddr)
+    };
+    Ok((addr, it))
+}
+
 fn char_no_quoted(c: char) -> bool {
     c.is_alphanumeric() || c == '.' || c == '#' || c == '_' || c == '.'
 }
@@ -729,10 +870,8 @@ impl UnitAsBytes for Unit {
                 .chain(msg.clone().as_bytes())
                 .chain((serv.len() as u32).to_le_bytes())
                 .chain(serv.as_bytes().into_iter().cloned())
-                .chain(match addr.as_ref() {
-                    Addr::Local => vec![UnitBin::AddrLoc as u8],
-                    Addr::Remote(addr) => [UnitBin::AddrRemote as u8].into_iter().chain(addr.into_iter().flat_map(|e| e.to_le_bytes())).collect::<Vec<u8>>()
-                }).collect(),
+                .chain(addr_as_bytes(addr))
+                .collect(),
             UnitBase::Pair(u0, u1) => {
                 if let Some((u0, u1)) = u0.clone().as_uint().and_then(|u0| Some((u0, u1.clone().as_uint()?))) {
                     if u1 <= 16777215 {
@@ -880,7 +1019,8 @@ impl<'a> UnitParse<'a, u8, Iter<'a, u8>> for Unit {
                     0,
                     0
                 ];
-                let v = <i32>::from_le_bytes(bytes);
+                // sign extend
+                let v = <i32>::from_le_bytes(bytes) << 24 >> 24;
                 Ok((Unit::int(v), it))
             },
             _b if _b == UnitBin::Int16 as u8 => {
@@ -890,7 +1030,8 @@ impl<'a> UnitParse<'a, u8, Iter<'a, u8>> for Unit {
                     0,
                     0
                 ];
-                let v = <i32>::from_le_bytes(bytes);
+                // sign extend
+                let v = <i32>::from_le_bytes(bytes) << 16 >> 16;
                 Ok((Unit::int(v), it))
             },
             _b if _b == UnitBin::Int24 as u8 => {
@@ -900,7 +1041,8 @@ impl<'a> UnitParse<'a, u8, Iter<'a, u8>> for Unit {
                     *it.next().ok_or(UnitParseErr::UnexpectedEnd)?,
                     0
                 ];
-                let v = <i32>::from_le_bytes(bytes);
+                // sign extend
+                let v = <i32>::from_le_bytes(bytes) << 8 >> 8;
                 Ok((Unit::int(v), it))
             },
             _b if _b == UnitBin::IntNat as u8 => {
@@ -1107,24 +1249,7 @@ impl<'a> UnitParse<'a, u8, Iter<'a, u8>> for Unit {
         let serv = String::from_utf8(bytes).map_err(|_| UnitParseErr::NotStr)?;
 
         // addr
-        let addr = match *it.next().ok_or(UnitParseErr::UnexpectedEnd)? {
-            _b if _b == UnitBin::AddrLoc as u8 => Addr::Local,
-            _b if _b == UnitBin::AddrRemote as u8 => {
-                let addr = (0..8).map(|_| {
-                    let bytes = [
-                        *it.next()?,
-                        *it.next()?
-                    ];
-                    Some(<u16>::from_le_bytes(bytes))
-                }).try_collect::<Vec<_>>()
-                    .ok_or(UnitParseErr::UnexpectedEnd)?
-                    .try_into()
-                    .map_err(|_| UnitParseErr::UnexpectedEnd)?;
-
-                Addr::Remote(addr)
-            },
-            _ => return Err(UnitParseErr::InvalidAddr)
-        };
+        let (addr, it) = parse_addr_bytes(it)?;
 
         Ok((Unit::stream(msg, &serv, addr), it))
     }
@@ -1315,11 +1440,11 @@ impl<'a> UnitParse<'a, u8, Iter<'a, u8>> for Unit {
 
 impl<I> UnitParse<'_, char, I> for Unit where I: Iterator<Item = char> + Clone {
     fn parse(it: I) -> Result<(Unit, I), UnitParseErr> {
-        if let Ok((u, it)) = Self::parse_stream(it.clone()) {
-            return Ok((u, it))
+        match Self::parse_stream(it) {
+            Err(UnitParseErr::InvalidAddr) => Err(UnitParseErr::InvalidAddr),
+            Err(..) => Err(UnitParseErr::NotUnit),
+            res => res
         }
-
-        Err(UnitParseErr::NotUnit)
     }
 
     fn parse_ch(expect: char, mut it: I) -> Result<I, UnitParseErr> {
@@ -1506,7 +1631,14 @@ impl<I> UnitParse<'_, char, I> for Unit where I: Iterator<Item = char> + Clone {
             let (serv, tmp) = Unit::parse_str(tmp)?;
             let serv = serv.as_str().ok_or(UnitParseErr::StreamInvalidServ)?;
 
-            // FIXME: add `addr`
+            // `:loc`, `:0x..:..:0x..`, `:node` or `:hop>hop`, invalid address is error
+            if Unit::parse_ch(':', tmp.clone()).is_ok() {
+                let (addr, tmp) = parse_addr(tmp)?;
+
+                (u, it) = (Unit::stream(u, &serv, addr), tmp);
+                continue;
+            }
+
             (u, it) = (Unit::stream_loc(u, &serv), tmp);
         }
         Ok((u, it))
@@ -1649,14 +1781,54 @@ impl UnitModify for Unit {
         }
     }
 
-    fn merge<'a, I>(self, path: I, what: Unit) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone {
-        // FIXME: make it work
-        let what = if let Some(u) = self.find(path.clone()) {
-            u.merge_with(what)
+    fn remove<'a, I>(self, mut path: I) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone {
+        let step = path.next()?;
+
+        // remove child
+        if path.clone().next().is_none() {
+            return match self.0.as_ref() {
+                UnitBase::List(lst) => {
+                    let idx = step.parse::<usize>().ok()?;
+                    if idx >= lst.len() {
+                        return None;
+                    }
+
+                    let lst = lst.iter().cloned().enumerate().filter(|(i, _)| *i != idx).map(|(_, u)| u).collect::<Vec<_>>();
+                    Some(Unit::list(&lst))
+                },
+                UnitBase::Map(map) => {
+                    if let None = map.iter().filter_map(|(u0, _)| u0.clone().as_str()).find(|s| s.as_str() == step) {
+                        return None
+                    }
+
+                    let map = map.iter().cloned().filter(|(u0, _)| u0.clone().as_str().filter(|s| s.as_str() == step).is_none()).collect::<Vec<_>>();
+                    Some(Unit::map(&map))
+                },
+                _ => None
+            }
+        }
+
+        let u = self.find([step].into_iter())?.remove(path)?;
+        self.replace([step].into_iter(), u)
+    }
+
+    fn merge<'a, I>(self, mut path: I, what: Unit) -> Option<Unit> where I: Iterator<Item = &'a str> + Clone {
+        let step = if let Some(step) = path.next() {
+            step
         } else {
-            what
+            return Some(self.merge_with(what));
         };
-        self.replace(path, what)
+
+        if let Some(u) = self.find([step].into_iter()) {
+            return self.replace([step].into_iter(), u.merge(path, what)?)
+        }
+
+        // create missing path
+        let mut map = Rc::unwrap_or_clone(self.as_map()?);
+        let u = path.collect::<Vec<_>>().into_iter().rev().fold(what, |u, s| Unit::map(&[(Unit::str(s), u)]));
+
+        map.push((Unit::str(step), u));
+        Some(Unit::map(&map))
     }
 
     fn merge_with(self, what: Unit) -> Unit {
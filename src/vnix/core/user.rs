@@ -3,29 +3,34 @@ use alloc::string::{String, ToString};
 use core::fmt::{Display, Formatter};
 
 use sha3::{Digest, Sha3_256};
+use p256::SecretKey;
 use p256::ecdsa::{SigningKey, VerifyingKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
 use p256::ecdsa::signature::{Signature, Signer, Verifier};
 
 use base64ct::{Base64, Encoding};
 
 use crate::vnix::core::driver::DrvErr;
+use crate::vnix::utils::{encrypt_raw, decrypt_raw};
 
 use super::kern::{KernErr, Kern};
-use super::unit::{Unit, UnitAsBytes};
+use super::unit::{Unit, UnitAsBytes, UnitParse};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Usr {
     pub name: String,
     pub pub_key: String, // sec1: elliptic curve
-    priv_key: Option<String>
+    pub enc_pub_key: String, // sec1: elliptic curve, for key agreement
+    priv_key: Option<String>,
+    enc_priv_key: Option<String>
 }
 
 impl Display for Usr {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         if self.name.contains(" ") {
-            write!(f, "{{ath:`{}` pub:{} priv:{}}}", self.name, self.pub_key, if self.priv_key.is_some() {".."} else {"-"})
+            write!(f, "{{ath:`{}` pub:{} pub.enc:{} priv:{}}}", self.name, self.pub_key, self.enc_pub_key, if self.priv_key.is_some() {".."} else {"-"})
         } else {
-            write!(f, "{{ath:{} pub:{} priv:{}}}", self.name, self.pub_key, if self.priv_key.is_some() {".."} else {"-"})
+            write!(f, "{{ath:{} pub:{} pub.enc:{} priv:{}}}", self.name, self.pub_key, self.enc_pub_key, if self.priv_key.is_some() {".."} else {"-"})
         }
     }
 }
@@ -42,46 +47,69 @@ impl Usr {
         let v = VerifyingKey::from(&p);
         let pub_key_b: [u8; 33] = v.to_encoded_point(true).as_bytes().try_into().map_err(|_| KernErr::CreatePubKeyFault)?;
 
+        // gen encryption key pair
+        let mut enc_priv_key_b: [u8; 32] = [0; 32];
+        kern.drv.rnd.get_bytes(&mut enc_priv_key_b).map_err(|e| KernErr::DrvErr(DrvErr::Rnd(e)))?;
+
+        let p = SecretKey::from_be_bytes(&enc_priv_key_b).map_err(|_| KernErr::CreatePrivKeyFault)?;
+        let enc_pub_key_b: [u8; 33] = p.public_key().to_encoded_point(true).as_bytes().try_into().map_err(|_| KernErr::CreatePubKeyFault)?;
+
         // encode base64
         let priv_key = Base64::encode_string(&priv_key_b); 
         let pub_key = Base64::encode_string(&pub_key_b);
 
-        let out = format!("{{ath:`{}` pub:`{}` priv:`{}`}}", name, pub_key, priv_key);
+        let enc_priv_key = Base64::encode_string(&enc_priv_key_b);
+        let enc_pub_key = Base64::encode_string(&enc_pub_key_b);
+
+        let out = format!("{{ath:`{}` pub:`{}` priv:`{}` pub.enc:`{}` priv.enc:`{}`}}", name, pub_key, priv_key, enc_pub_key, enc_priv_key);
 
         Ok((
             Usr {
                 name: name.into(),
                 priv_key: Some(priv_key),
-                pub_key
+                enc_priv_key: Some(enc_priv_key),
+                pub_key,
+                enc_pub_key
             },
             out
         ))
     }
 
-    pub fn guest(name: &str, pub_key: &str) -> Result<Self, KernErr> {
+    pub fn guest(name: &str, pub_key: &str, enc_pub_key: &str) -> Result<Self, KernErr> {
         Ok(Usr {
             name: name.to_string(),
             priv_key: None,
-            pub_key: pub_key.to_string()
+            enc_priv_key: None,
+            pub_key: pub_key.to_string(),
+            enc_pub_key: enc_pub_key.to_string()
         })
     }
 
-    pub fn login(name: &str, priv_key:&str, pub_key: &str) -> Result<Self, KernErr> {
+    pub fn login(name: &str, priv_key: &str, pub_key: &str, enc_priv_key: &str, enc_pub_key: &str) -> Result<Self, KernErr> {
         Ok(Usr {
             name: name.to_string(),
             priv_key: Some(priv_key.to_string()),
-            pub_key: pub_key.to_string()
+            enc_priv_key: Some(enc_priv_key.to_string()),
+            pub_key: pub_key.to_string(),
+            enc_pub_key: enc_pub_key.to_string()
         })
     }
 
+    // guest has no private key
+    pub fn can_sign(&self) -> bool {
+        self.priv_key.is_some()
+    }
+
     pub fn sign(&self, u: Unit) -> Result<String, KernErr> {
+        self.sign_bytes(&u.as_bytes())
+    }
+
+    pub fn sign_bytes(&self, msg: &[u8]) -> Result<String, KernErr> {
         if let Some(priv_key_s) = &self.priv_key {
             let priv_key_b = Base64::decode_vec(priv_key_s.as_str()).map_err(|_| KernErr::DecodeFault)?;
             let priv_key = SigningKey::from_bytes(priv_key_b.as_slice()).map_err(|_| KernErr::CreatePrivKeyFault)?;
 
-            let msg = u.clone().as_bytes();
-
-            let sign_b = priv_key.sign(&msg);
+            let sign_b = priv_key.sign(msg);
             let sign = Base64::encode_string(&sign_b.as_bytes());
 
             return Ok(sign)
@@ -89,13 +117,31 @@ impl Usr {
         Err(KernErr::SignFault)
     }
 
-    pub fn verify(&self, u: Unit, sign: &str, hash: &str) -> Result<(), KernErr> {
-        let sign_b = Base64::decode_vec(sign).map_err(|_| KernErr::DecodeFault)?;
-        let sign = Signature::from_bytes(&sign_b.as_slice()).map_err(|_| KernErr::SignVerifyFault)?;
+    pub fn encrypt(&self, u: Unit, kern: &mut Kern) -> Result<String, KernErr> {
+        let pub_key_b = Base64::decode_vec(self.enc_pub_key.as_str()).map_err(|_| KernErr::DecodeFault)?;
 
-        let pub_key_b = Base64::decode_vec(self.pub_key.as_str()).map_err(|_| KernErr::DecodeFault)?;
-        let pub_key = VerifyingKey::from_sec1_bytes(&pub_key_b.as_slice()).map_err(|_| KernErr::CreatePubKeyFault)?;
+        // ephemeral key
+        let mut eph_key_b: [u8; 32] = [0; 32];
+        kern.drv.rnd.get_bytes(&mut eph_key_b).map_err(|e| KernErr::DrvErr(DrvErr::Rnd(e)))?;
+
+        let dat = encrypt_raw(&pub_key_b, &eph_key_b, &u.as_bytes())?;
+        Ok(Base64::encode_string(&dat))
+    }
 
+    pub fn decrypt(&self, s: &str) -> Result<Unit, KernErr> {
+        if let Some(priv_key_s) = &self.enc_priv_key {
+            let priv_key_b = Base64::decode_vec(priv_key_s.as_str()).map_err(|_| KernErr::DecodeFault)?;
+            let dat = Base64::decode_vec(s).map_err(|_| KernErr::DecodeFault)?;
+
+            let dat = decrypt_raw(&priv_key_b, &dat)?;
+            let u = Unit::parse(dat.iter()).map_err(|e| KernErr::ParseErr(e))?.0;
+
+            return Ok(u)
+        }
+        Err(KernErr::DecryptFault)
+    }
+
+    pub fn verify(&self, u: Unit, sign: &str, hash: &str) -> Result<(), KernErr> {
         let msg = u.clone().as_bytes();
 
         let h = Sha3_256::digest(&msg);
@@ -105,6 +151,16 @@ impl Usr {
             return Err(KernErr::HashVerifyFault);
         }
 
-        pub_key.verify(&msg, &sign).map_err(|_| KernErr::SignVerifyFault)
+        self.verify_bytes(&msg, sign)
+    }
+
+    pub fn verify_bytes(&self, msg: &[u8], sign: &str) -> Result<(), KernErr> {
+        let sign_b = Base64::decode_vec(sign).map_err(|_| KernErr::DecodeFault)?;
+        let sign = Signature::from_bytes(&sign_b.as_slice()).map_err(|_| KernErr::SignVerifyFault)?;
+
+        let pub_key_b = Base64::decode_vec(self.pub_key.as_str()).map_err(|_| KernErr::DecodeFault)?;
+        let pub_key = VerifyingKey::from_sec1_bytes(&pub_key_b.as_slice()).map_err(|_| KernErr::CreatePubKeyFault)?;
+
+        pub_key.verify(msg, &sign).map_err(|_| KernErr::SignVerifyFault)
     }
 }
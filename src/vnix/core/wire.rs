@@ -0,0 +1,387 @@
+use alloc::vec::Vec;
+use alloc::string::{String, ToString};
+
+use crate::vnix::core::driver::MemSizeUnits;
+
+use super::msg::Msg;
+use super::user::Usr;
+use super::peer::NODE_USR;
+use super::kern::KernErr;
+use super::unit::{Unit, UnitAsBytes, UnitParse};
+
+
+// frame: `<magic><version><kind><id: u64 le><nonce: u64 le><field>*<sign>`
+// field: `<len: u32 le><bytes>`
+// request: `<serv><route: 8 x u16 le per hop><msg>`
+// sign: node key signature of all bytes before it
+pub const WIRE_MAGIC: [u8; 4] = *b"VNXM";
+pub const WIRE_VERSION: u8 = 3;
+
+// queue: `<field>*`, entry: `<addr: 8 x u16 le><tries: u32 le><frame>`
+pub const NET_QUEUE_NAME: &'static str = "vnix.net.que";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+enum WireKind {
+    Req = 0,
+    Res = 1,
+    ResNone = 2,
+    ResErr = 3
+}
+
+#[derive(Debug)]
+pub enum NetFrame {
+    Req {
+        id: usize,
+        serv: String,
+        route: Vec<[u16; 8]>,
+        msg: Msg
+    },
+    Res {
+        id: usize,
+        res: Result<Option<Msg>, String>
+    }
+}
+
+// request waiting for unreachable node
+#[derive(Debug, Clone)]
+pub struct NetPending {
+    pub id: usize,
+    pub addr: [u16; 8],
+    pub serv: String,
+    pub ath: String,
+    pub tries: u32,
+    pub next: u128,
+    pub frame: Vec<u8>
+}
+
+struct WireReader<'a> {
+    dat: &'a [u8]
+}
+
+fn put_field(out: &mut Vec<u8>, dat: &[u8]) {
+    out.extend((dat.len() as u32).to_le_bytes());
+    out.extend_from_slice(dat);
+}
+
+// `<ath><to><hash><sign><payload>`, empty `to` for plain message
+fn put_msg(out: &mut Vec<u8>, msg: &Msg) {
+    put_field(out, msg.ath.as_bytes());
+    put_field(out, msg.to.as_deref().unwrap_or("").as_bytes());
+    put_field(out, msg.hash.as_bytes());
+    put_field(out, msg.sign.as_bytes());
+    put_field(out, &msg.msg.clone().as_bytes());
+}
+
+impl<'a> WireReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], KernErr> {
+        if self.dat.len() < len {
+            return Err(KernErr::WireFault)
+        }
+
+        let (dat, rest) = self.dat.split_at(len);
+        self.dat = rest;
+
+        Ok(dat)
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], KernErr> {
+        let len = u32::from_le_bytes(self.take(4)?.try_into().map_err(|_| KernErr::WireFault)?);
+        self.take(len as usize)
+    }
+
+    fn str(&mut self) -> Result<String, KernErr> {
+        core::str::from_utf8(self.field()?).map(|s| s.to_string()).map_err(|_| KernErr::WireFault)
+    }
+
+    // relays after receiver, last is destination
+    fn route(&mut self) -> Result<Vec<[u16; 8]>, KernErr> {
+        let dat = self.field()?;
+
+        if dat.len() % 16 != 0 {
+            return Err(KernErr::WireFault)
+        }
+
+        let route = dat.chunks(16).map(|hop| {
+            let mut addr = [0; 8];
+
+            for (e, b) in addr.iter_mut().zip(hop.chunks(2)) {
+                *e = u16::from_le_bytes([b[0], b[1]]);
+            }
+            addr
+        }).collect();
+
+        Ok(route)
+    }
+
+    fn msg(&mut self) -> Result<Msg, KernErr> {
+        let ath = self.str()?;
+        let to = Some(self.str()?).filter(|s| !s.is_empty());
+        let hash = self.str()?;
+        let sign = self.str()?;
+
+        let (msg, rest) = Unit::parse(self.field()?.iter()).map_err(|e| KernErr::ParseErr(e))?;
+
+        if rest.len() != 0 {
+            return Err(KernErr::WireFault)
+        }
+
+        let size = msg.size(MemSizeUnits::Bytes);
+        Ok(Msg {ath, to, hash, sign, msg, size})
+    }
+}
+
+impl NetFrame {
+    // nonce must be unique, so receiver can drop replayed frame
+    pub fn encode(&self, nonce: u64, node: &Usr) -> Result<Vec<u8>, KernErr> {
+        let mut out = WIRE_MAGIC.to_vec();
+        out.push(WIRE_VERSION);
+
+        match self {
+            NetFrame::Req{id, serv, route, msg} => {
+                out.push(WireKind::Req as u8);
+                out.extend((*id as u64).to_le_bytes());
+                out.extend(nonce.to_le_bytes());
+
+                put_field(&mut out, serv.as_bytes());
+                put_field(&mut out, &route.iter().flatten().flat_map(|e| e.to_le_bytes()).collect::<Vec<_>>());
+                put_msg(&mut out, msg);
+            },
+            NetFrame::Res{id, res} => {
+                let kind = match res {
+                    Ok(Some(..)) => WireKind::Res,
+                    Ok(None) => WireKind::ResNone,
+                    Err(..) => WireKind::ResErr
+                };
+
+                out.push(kind as u8);
+                out.extend((*id as u64).to_le_bytes());
+                out.extend(nonce.to_le_bytes());
+
+                match res {
+                    Ok(Some(msg)) => put_msg(&mut out, msg),
+                    Ok(None) => (),
+                    Err(e) => put_field(&mut out, e.as_bytes())
+                }
+            }
+        }
+
+        let sign = node.sign_bytes(&out)?;
+        put_field(&mut out, sign.as_bytes());
+
+        Ok(out)
+    }
+
+    // frame, nonce and length of signed bytes
+    fn parse(dat: &[u8]) -> Result<(Self, u64, usize), KernErr> {
+        let mut rd = WireReader {dat};
+
+        if rd.take(4)? != WIRE_MAGIC {
+            return Err(KernErr::WireFault)
+        }
+
+        if rd.take(1)?[0] != WIRE_VERSION {
+            return Err(KernErr::WireFault)
+        }
+
+        let kind = rd.take(1)?[0];
+        let id = u64::from_le_bytes(rd.take(8)?.try_into().map_err(|_| KernErr::WireFault)?) as usize;
+        let nonce = u64::from_le_bytes(rd.take(8)?.try_into().map_err(|_| KernErr::WireFault)?);
+
+        let frame = match kind {
+            _k if _k == WireKind::Req as u8 => NetFrame::Req{id, serv: rd.str()?, route: rd.route()?, msg: rd.msg()?},
+            _k if _k == WireKind::Res as u8 => NetFrame::Res{id, res: Ok(Some(rd.msg()?))},
+            _k if _k == WireKind::ResNone as u8 => NetFrame::Res{id, res: Ok(None)},
+            _k if _k == WireKind::ResErr as u8 => NetFrame::Res{id, res: Err(rd.str()?)},
+            _ => return Err(KernErr::WireFault)
+        };
+
+        let len = dat.len() - rd.dat.len();
+        rd.field()?;
+
+        // trailing bytes
+        if !rd.dat.is_empty() {
+            return Err(KernErr::WireFault)
+        }
+        Ok((frame, nonce, len))
+    }
+
+    // frame with its nonce, signature is not checked
+    pub fn decode(dat: &[u8]) -> Result<(Self, u64), KernErr> {
+        Self::parse(dat).map(|(frame, nonce, _)| (frame, nonce))
+    }
+
+    // decode frame signed by node with known public key
+    pub fn open(dat: &[u8], key: &str) -> Result<(Self, u64), KernErr> {
+        let (frame, nonce, len) = Self::parse(dat)?;
+
+        let (signed, sign) = dat.split_at(len);
+        let sign = core::str::from_utf8(&sign[4..]).map_err(|_| KernErr::WireFault)?;
+
+        let node = Usr::guest(NODE_USR, key, key)?;
+        node.verify_bytes(signed, sign)?;
+
+        Ok((frame, nonce))
+    }
+}
+
+impl NetPending {
+    pub fn new(addr: [u16; 8], frame: Vec<u8>) -> Result<Self, KernErr> {
+        match NetFrame::decode(&frame)?.0 {
+            NetFrame::Req{id, serv, msg, ..} => Ok(NetPending {id, addr, serv, ath: msg.ath, tries: 0, next: 0, frame}),
+            _ => Err(KernErr::WireFault)
+        }
+    }
+
+    // retry delay in milliseconds
+    pub fn backoff(&self) -> u128 {
+        (500 << self.tries.min(7)) as u128
+    }
+
+    pub fn encode_queue(queue: &[NetPending]) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        for p in queue {
+            let dat = p.addr.iter().flat_map(|e| e.to_le_bytes())
+                .chain(p.tries.to_le_bytes())
+                .chain(p.frame.iter().cloned())
+                .collect::<Vec<_>>();
+
+            put_field(&mut out, &dat);
+        }
+        out
+    }
+
+    pub fn decode_queue(dat: &[u8]) -> Result<Vec<Self>, KernErr> {
+        let mut rd = WireReader {dat};
+        let mut queue = Vec::new();
+
+        while !rd.dat.is_empty() {
+            let mut ent = WireReader {dat: rd.field()?};
+
+            let mut addr = [0; 8];
+
+            for e in addr.iter_mut() {
+                *e = u16::from_le_bytes(ent.take(2)?.try_into().map_err(|_| KernErr::WireFault)?);
+            }
+
+            let tries = u32::from_le_bytes(ent.take(4)?.try_into().map_err(|_| KernErr::WireFault)?);
+
+            // frames of other wire version are dropped
+            if let Ok(mut p) = NetPending::new(addr, ent.dat.to_vec()) {
+                p.tries = tries;
+                queue.push(p);
+            }
+        }
+        Ok(queue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use alloc::string::String;
+
+    use base64ct::{Base64, Encoding};
+    use p256::ecdsa::{SigningKey, VerifyingKey};
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+    use super::{NetFrame, NetPending};
+    use super::super::msg::Msg;
+    use super::super::user::Usr;
+    use super::super::unit::{Unit, UnitNew};
+
+    const ADDR: [u16; 8] = [0, 0, 0, 0, 0, 0, 0, 2];
+
+    fn usr(name: &str, seed: u8) -> Usr {
+        let priv_key = SigningKey::from_bytes(&[seed; 32]).unwrap();
+        let pub_key = VerifyingKey::from(&priv_key).to_encoded_point(true);
+
+        let priv_key = Base64::encode_string(&priv_key.to_bytes());
+        let pub_key = Base64::encode_string(pub_key.as_bytes());
+
+        Usr::login(name, &priv_key, &pub_key, &priv_key, &pub_key).unwrap()
+    }
+
+    fn req() -> NetFrame {
+        let msg = Msg::new(usr("alice", 2), Unit::pair(Unit::str("sum"), Unit::list(&[Unit::uint(1), Unit::uint(2)]))).unwrap();
+        NetFrame::Req{id: 7, serv: "math.calc".into(), route: Vec::from([ADDR]), msg}
+    }
+
+    #[test]
+    fn req_round_trip() {
+        let node = usr("node", 1);
+        let dat = req().encode(42, &node).unwrap();
+
+        let (frame, nonce) = NetFrame::open(&dat, &node.pub_key).unwrap();
+        assert_eq!(nonce, 42);
+
+        let NetFrame::Req{id, serv, route, msg} = frame else {
+            panic!("request expected")
+        };
+        let NetFrame::Req{msg: orig, ..} = req() else {
+            unreachable!()
+        };
+
+        assert_eq!((id, serv.as_str(), route), (7, "math.calc", Vec::from([ADDR])));
+        assert_eq!((msg.ath, msg.to, msg.hash, msg.sign, msg.msg), (orig.ath, orig.to, orig.hash, orig.sign, orig.msg));
+    }
+
+    #[test]
+    fn res_round_trip() {
+        let node = usr("node", 1);
+        let msg = Msg::new(usr("alice", 2), Unit::uint(3)).unwrap();
+
+        let frames = [
+            NetFrame::Res{id: 1, res: Ok(Some(msg))},
+            NetFrame::Res{id: 2, res: Ok(None)},
+            NetFrame::Res{id: 3, res: Err(String::from("ServNotFound"))}
+        ];
+
+        for (i, frame) in frames.into_iter().enumerate() {
+            let dat = frame.encode(i as u64, &node).unwrap();
+
+            match NetFrame::open(&dat, &node.pub_key).unwrap() {
+                (NetFrame::Res{id: 1, res: Ok(Some(msg))}, 0) => assert_eq!(msg.msg, Unit::uint(3)),
+                (NetFrame::Res{id: 2, res: Ok(None)}, 1) => (),
+                (NetFrame::Res{id: 3, res: Err(e)}, 2) => assert_eq!(e, "ServNotFound"),
+                (frame, nonce) => panic!("unexpected frame {frame:?} with nonce {nonce}")
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_frame_rejected() {
+        let node = usr("node", 1);
+        let dat = req().encode(42, &node).unwrap();
+
+        // every byte is covered by signature or breaks framing
+        for i in 0..dat.len() {
+            let mut bad = dat.clone();
+            bad[i] ^= 0x01;
+
+            assert!(NetFrame::open(&bad, &node.pub_key).is_err(), "byte {i} is not covered");
+        }
+
+        assert!(NetFrame::open(&dat[..dat.len() - 1], &node.pub_key).is_err());
+        assert!(NetFrame::open(&[dat.as_slice(), &[0]].concat(), &node.pub_key).is_err());
+    }
+
+    #[test]
+    fn foreign_node_rejected() {
+        let dat = req().encode(42, &usr("node", 1)).unwrap();
+        assert!(NetFrame::open(&dat, &usr("node", 3).pub_key).is_err());
+    }
+
+    #[test]
+    fn queue_round_trip() {
+        let node = usr("node", 1);
+        let mut p = NetPending::new(ADDR, req().encode(42, &node).unwrap()).unwrap();
+        p.tries = 3;
+
+        let queue = NetPending::decode_queue(&NetPending::encode_queue(&[p])).unwrap();
+
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0].id, queue[0].addr, queue[0].serv.as_str(), queue[0].ath.as_str(), queue[0].tries), (7, ADDR, "math.calc", "alice", 3));
+    }
+}
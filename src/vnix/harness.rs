@@ -0,0 +1,141 @@
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::format;
+use alloc::string::String;
+
+use spin::Mutex;
+
+use crate::content;
+use crate::driver::stub::{StubCLI, StubDisp, StubTime, StubMem, StubDisk, PRng, LoopHub, LoopNet};
+
+use super::vnix_init;
+use super::utils::STORE_NAME;
+use super::serv::io::term::{base, Mode};
+use super::core::peer::Peer;
+use super::core::user::Usr;
+use super::core::task::TaskRun;
+use super::core::serv::ServResult;
+use super::core::kern::{Kern, KernDrv, Sched};
+use super::core::unit::{Unit, UnitParse, UnitAs};
+
+
+pub fn node(n: u16, hub: Arc<Mutex<LoopHub>>) -> Kern {
+    let disk = StubDisk {blobs: Vec::from([(STORE_NAME.into(), b"{}".to_vec())])};
+
+    let drv = KernDrv::new(
+        Box::new(StubCLI),
+        Box::new(StubDisp),
+        Box::new(StubTime::default()),
+        Box::new(PRng([n as u8; 32])),
+        Box::new(StubMem),
+        Box::new(disk),
+        Box::new(LoopNet::new([0, 0, 0, 0, 0, 0, 0, n], hub))
+    );
+
+    let term = Rc::new(Mutex::new(base::Term::new(&content::SYS_FONT)));
+    term.lock().mode = Mode::Text;
+
+    let mut kern = Kern::new(drv, term);
+    vnix_init(&mut kern).unwrap();
+
+    kern
+}
+
+pub fn peer(name: &str, kern: &Kern) -> Peer {
+    Peer {
+        name: name.into(),
+        addr: kern.drv.net.addr(),
+        key: kern.get_node().unwrap().pub_key.clone()
+    }
+}
+
+// local user with own keys
+pub fn usr(kern: &Mutex<Kern>, name: &str) {
+    let mut grd = kern.lock();
+    let usr = Usr::new(name, &mut grd).unwrap().0;
+
+    grd.reg_usr(usr).unwrap();
+}
+
+// nodes know each other as `node1` and `node2`
+fn pair(hub1: Arc<Mutex<LoopHub>>, hub2: Arc<Mutex<LoopHub>>) -> [Mutex<Kern>; 2] {
+    let mut node1 = node(1, hub1);
+    let mut node2 = node(2, hub2);
+
+    let (peer1, peer2) = (peer("node1", &node1), peer("node2", &node2));
+
+    node1.set_peers(Vec::from([peer2])).unwrap();
+    node2.set_peers(Vec::from([peer1])).unwrap();
+
+    [Mutex::new(node1), Mutex::new(node2)]
+}
+
+// two nodes on one hub
+pub fn cluster() -> [Mutex<Kern>; 2] {
+    let hub = Arc::new(Mutex::new(LoopHub::default()));
+    pair(hub.clone(), hub)
+}
+
+// two nodes on different hubs, so they can't reach each other
+pub fn apart() -> [Mutex<Kern>; 2] {
+    pair(Arc::default(), Arc::default())
+}
+
+// schedulers of both nodes, each step runs both of them
+pub struct Nodes<'a> {
+    pub sched: [Sched<'a>; 2]
+}
+
+impl<'a> Nodes<'a> {
+    pub fn new(kerns: &'a [Mutex<Kern>; 2]) -> Self {
+        Nodes {
+            sched: [Sched::new(&kerns[0]), Sched::new(&kerns[1])]
+        }
+    }
+
+    pub fn task(&self, kern: &Mutex<Kern>, ath: &str, msg: &str, serv: &str) -> usize {
+        let msg = Unit::parse(msg.chars()).unwrap().0;
+        kern.lock().reg_task(ath, "test", TaskRun(msg, serv.into())).unwrap()
+    }
+
+    // step both kernels until task is done
+    pub fn wait(&mut self, kern: &Mutex<Kern>, id: usize) -> ServResult {
+        for _ in 0..10000 {
+            self.step();
+
+            if let Some(res) = kern.lock().get_task_result(id) {
+                return res
+            }
+        }
+        panic!("task #{id} is not done")
+    }
+
+    pub fn run(&mut self, kern: &Mutex<Kern>, ath: &str, msg: &str, serv: &str) -> ServResult {
+        let id = self.task(kern, ath, msg, serv);
+        self.wait(kern, id)
+    }
+
+    pub fn step(&mut self) {
+        for s in self.sched.iter_mut() {
+            s.step().unwrap();
+        }
+    }
+
+    pub fn settle(&mut self) {
+        for _ in 0..100 {
+            self.step();
+        }
+    }
+}
+
+pub fn res_str(res: ServResult) -> String {
+    let msg = res.unwrap().unwrap();
+    format!("{}", msg.msg.as_map_find("msg").unwrap())
+}
+
+pub fn load(kern: &Mutex<Kern>, path: &str) -> Option<String> {
+    let path = Unit::parse(path.chars()).unwrap().0;
+    kern.lock().ram_store.load(path).map(|u| format!("{u}"))
+}
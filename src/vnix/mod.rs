@@ -2,6 +2,12 @@ pub mod core;
 pub mod serv;
 pub mod utils;
 
+#[cfg(test)]
+mod harness;
+
+#[cfg(test)]
+mod tests;
+
 use ::core::writeln;
 use ::core::fmt::Write;
 
@@ -9,14 +15,17 @@ use crate::vnix::core::driver::{CLIErr, DrvErr};
 
 use self::core::user::Usr;
 use self::core::task::TaskRun;
-use self::core::kern::{Kern, KernErr};
+use self::core::kern::{Kern, KernErr, Addr};
 use self::core::serv::{Serv, ServHlr};
 use self::core::unit::{Unit, UnitParse};
 
+use self::utils::STORE_NAME;
+
 use self::serv::{io, sys, math, gfx, dat, time, test};
 
 
-pub fn vnix_entry(mut kern: Kern) -> Result<(), KernErr> {
+// register services, load config and login `super`
+pub fn vnix_init(kern: &mut Kern) -> Result<Usr, KernErr> {
     // register service
     let services = [
         (io::term::SERV_PATH, io::term::help::SERV_HELP, Box::new(io::term::TermHlr) as Box<dyn ServHlr>),
@@ -29,6 +38,8 @@ pub fn vnix_entry(mut kern: Kern) -> Result<(), KernErr> {
         (math::calc::SERV_PATH,  math::calc::SERV_HELP, Box::new(math::calc::CalcHlr) as Box<dyn ServHlr>),
         (sys::task::SERV_PATH, sys::task::SERV_HELP, Box::new(sys::task::TaskHlr) as Box<dyn ServHlr>),
         (sys::usr::SERV_PATH, sys::usr::SERV_HELP, Box::new(sys::usr::UsrHlr) as Box<dyn ServHlr>),
+        (sys::pol::SERV_PATH, sys::pol::SERV_HELP, Box::new(sys::pol::PolHlr) as Box<dyn ServHlr>),
+        (sys::net::SERV_PATH, sys::net::SERV_HELP, Box::new(sys::net::NetHlr) as Box<dyn ServHlr>),
         (sys::hw::SERV_PATH, sys::hw::SERV_HELP, Box::new(sys::hw::HWHlr) as Box<dyn ServHlr>),
         (test::dump::SERV_PATH, test::dump::SERV_HELP, Box::new(test::dump::DumpHlr) as Box<dyn ServHlr>),
         (test::echo::SERV_PATH, test::echo::SERV_HELP, Box::new(test::echo::EchoHlr) as Box<dyn ServHlr>),
@@ -42,12 +53,37 @@ pub fn vnix_entry(mut kern: Kern) -> Result<(), KernErr> {
         writeln!(kern, "INFO vnix:kern: service `{}` registered", name).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
     }
 
+    // network
+    match kern.drv.net.listen() {
+        Ok(..) => writeln!(kern, "INFO vnix:kern: network listening on `{}`", Addr::Remote(kern.drv.net.addr())),
+        Err(e) => writeln!(kern, "WARN vnix:kern: network not available: {:?}", e)
+    }.map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+
+    // load store
+    kern.load_store()?;
+    writeln!(kern, "INFO vnix:kern: storage `{}` loaded", STORE_NAME).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+
+    // load services policy
+    kern.load_policy()?;
+    writeln!(kern, "INFO vnix:kern: services policy loaded").map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+
+    // load node key, network nodes and exported services
+    kern.load_node()?;
+    kern.load_net()?;
+    writeln!(kern, "INFO vnix:kern: network config loaded").map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
+
     // register user
-    let _super = Usr::new("super", &mut kern)?.0;
+    let _super = Usr::new("super", kern)?.0;
     kern.reg_usr(_super.clone())?;
 
     writeln!(kern, "INFO vnix:kern: user `{}` registered", _super).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
 
+    Ok(_super)
+}
+
+pub fn vnix_entry(mut kern: Kern) -> Result<(), KernErr> {
+    let _super = vnix_init(&mut kern)?;
+
     // test
     // let s = "{
     //     task.stk:[
@@ -80,7 +116,7 @@ pub fn vnix_entry(mut kern: Kern) -> Result<(), KernErr> {
 
     let run = TaskRun(msg, "sys.task".into());
 
-    kern.reg_task(&_super.name, "init.load", run)?;
+    kern.reg_task_detached(&_super.name, "init.load", run)?;
 
     kern.run()
 }
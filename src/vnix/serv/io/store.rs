@@ -1,19 +1,23 @@
 use alloc::rc::Rc;
+use alloc::vec::Vec;
 use alloc::boxed::Box;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 
 use spin::Mutex;
 use async_trait::async_trait;
 
-use crate::vnix::utils::Maybe;
-use crate::{read_async, as_map_find_async, as_async, maybe, maybe_ok};
+use num::BigInt;
+use num::traits::ToPrimitive;
+
+use crate::vnix::utils::{Maybe, RamStore, StoreFmt, StoreMode, StoreVer};
+use crate::{read_async, as_map_find_async, as_map_find_as_async, as_async, maybe, maybe_ok};
 
 use crate::vnix::core::msg::Msg;
 use crate::vnix::core::driver::MemSizeUnits;
 
 use crate::vnix::core::kern::{Kern, KernErr};
-use crate::vnix::core::serv::{ServHlr, ServInfo, ServResult};
-use crate::vnix::core::unit::{Unit, UnitNew, UnitAs, UnitTypeAsyncResult, UnitReadAsyncI, UnitAsyncResult};
+use crate::vnix::core::serv::{ServHlr, ServInfo, ServResult, ServErr};
+use crate::vnix::core::unit::{Unit, UnitNew, UnitAs, UnitModify, UnitTypeAsyncResult, UnitReadAsyncI, UnitAsyncResult, Path};
 
 
 pub const SERV_PATH: &'static str = "io.store";
@@ -40,6 +44,95 @@ pub const SERV_HELP: &'static str = "{
             com:(get.size.kb @img.vnix.logo)@io.store
             res:6
         }
+        {
+            info:`Write storage back to disk`
+            com:[
+                sync@io.store
+                flush@io.store
+            ]
+        }
+        {
+            info:`Enable write-through mode, so every save is written to disk`
+            com:(set.sync t)@io.store
+        }
+        {
+            info:`Convert storage to binary compressed format and write it to disk`
+            com:(conv bin)@io.store
+        }
+        {
+            info:`Get storage format`
+            com:get.fmt@io.store
+            res:bin
+        }
+        {
+            info:`Delete unit from storage`
+            com:[
+                (del @txt.test)@io.store
+                {del:@txt.test}@io.store
+            ]
+        }
+        {
+            info:`List keys of storage unit`
+            com:[
+                (ls @img.vnix)@io.store
+                (keys @img.vnix)@io.store
+            ]
+            res:[logo]
+        }
+        {
+            info:`Check if unit exists in storage`
+            com:(has @txt.hello)@io.store
+            res:t
+        }
+        {
+            info:`Move unit to another path in storage`
+            com:[
+                (mv (@txt.test @txt.old))@io.store
+                {mv:@txt.test out:@txt.old}@io.store
+            ]
+        }
+        {
+            info:`Apply several operations atomically, all or nothing`
+            com:{
+                txn:[
+                    (save ((load @img.vnix.logo)@io.store @img.logo))
+                    {save:{size:(32 32)} out:@img.meta.logo}
+                    (mv (@txt.test @txt.old))
+                    (del @txt.old)
+                ]
+            }@io.store
+        }
+        {
+            info:`Make unit private, so only owner and super can read it`
+            com:(set.mode (prv @txt.test))@io.store
+        }
+        {
+            info:`Give unit to another user`
+            com:(set.own (alice @txt.test))@io.store
+        }
+        {
+            info:`Get unit owner and access mode`
+            com:(get.acl @txt.test)@io.store
+            res:{own:alice mode:prv enc:f}
+        }
+        {
+            info:`Save text encrypted by owner key, only owner can load it`
+            com:{save:abc out:@txt.secret enc:t}@io.store
+        }
+        {
+            info:`Replicate units to all known nodes, only super can do it`
+            com:(set.repl (@shared t))@io.store
+        }
+        {
+            info:`Get replication status`
+            com:get.repl@io.store
+            res:{
+                paths:[@shared]
+                clock:12
+                ver:4
+                peers:[{name:laptop que:0} {name:pc que:2}]
+            }
+        }
     ]
     man:{
         load:{
@@ -52,11 +145,13 @@ pub const SERV_HELP: &'static str = "{
         }
         save:{
             info:`Save unit to storage`
+            enc:`encrypt unit by owner key, so it is stored encrypted in memory and on disk`
             schm:[
                 (save (unit @path))
                 {save:unit out:@path}
+                {save:unit out:@path enc:bool}
             ]
-            tut:@tut.2
+            tut:[@tut.2 @tut.16]
         }
         get.size:{
             info:`Get unit size in bytes from storage`
@@ -67,20 +162,250 @@ pub const SERV_HELP: &'static str = "{
             ]
             tut:@tut.3
         }
+        sync:{
+            info:`Write storage back to disk, only super can do it`
+            schm:[
+                sync
+                flush
+            ]
+            tut:@tut.4
+        }
+        set.sync:{
+            info:`Enable or disable write-through mode, only super can do it`
+            schm:(set.sync bool)
+            tut:@tut.5
+        }
+        conv:{
+            info:`Convert storage format and write it to disk, only super can do it`
+            fmt:[txt bin]
+            schm:(conv fmt)
+            tut:@tut.6
+        }
+        get.fmt:{
+            info:`Get storage format`
+            schm:get.fmt
+            tut:@tut.7
+        }
+        del:{
+            info:`Delete unit from storage`
+            schm:[
+                (del @path)
+                {del:@path}
+            ]
+            tut:@tut.8
+        }
+        ls:{
+            info:`List keys of storage unit`
+            schm:[
+                ls
+                keys
+                (ls @path)
+                (keys @path)
+            ]
+            tut:@tut.9
+        }
+        has:{
+            info:`Check if unit exists in storage`
+            schm:[
+                (has @path)
+                {has:@path}
+            ]
+            tut:@tut.10
+        }
+        mv:{
+            info:`Move unit to another path in storage`
+            schm:[
+                (mv (@path @path))
+                {mv:@path out:@path}
+            ]
+            tut:@tut.11
+        }
+        txn:{
+            info:`Apply several operations atomically, all or nothing`
+            ops:[save del mv]
+            schm:[
+                {txn:[op]}
+                (txn [op])
+            ]
+            tut:@tut.12
+        }
+        set.mode:{
+            info:`Set unit access mode, only owner or super can change it`
+            modes:{
+                prv:`only owner can read and write`
+                ro:`everyone can read, only owner can write`
+                pub:`everyone can read and write`
+            }
+            schm:(set.mode (mode @path))
+            tut:@tut.13
+        }
+        set.own:{
+            info:`Set unit owner, only owner or super can change it; encrypted unit is encrypted again by new owner key`
+            schm:(set.own (usr @path))
+            tut:@tut.14
+        }
+        get.acl:{
+            info:`Get unit owner and access mode, units without owner belong to super, only super can write @sys`
+            schm:[
+                (get.acl @path)
+                {get.acl:@path}
+            ]
+            tut:@tut.15
+        }
+        set.repl:{
+            info:`Enable or disable replication of units to known nodes from sys.net; every write is signed by node, keeps its author and has logical timestamp, so the latest write wins on all nodes`
+            schm:(set.repl (@path bool))
+            tut:@tut.17
+        }
+        get.repl:{
+            info:`Get replicated paths, logical clock, count of known writes and writes waiting for every node`
+            schm:get.repl
+            tut:@tut.18
+        }
+        repl:{
+            info:`Write sent by known node for its author, it is applied if author can write unit and there is no later write; later writes inside unit are kept`
+            schm:{repl:@path ts:uint usr:str val:unit}
+        }
     }
 }";
 
 pub struct StoreHlr;
 
+enum StoreOp {
+    Save(Unit, Rc<Path>),
+    Del(Rc<Path>),
+    Mv(Rc<Path>, Rc<Path>)
+}
+
 impl StoreHlr {
+    fn check(store: &RamStore, ath: &str, path: &Path, write: bool) -> Result<(), KernErr> {
+        let allowed = if write {
+            store.can_write(ath, path)
+        } else {
+            store.can_read(ath, path)
+        };
+
+        if !allowed {
+            return Err(KernErr::DbAccessDenied)
+        }
+        Ok(())
+    }
+
+    fn view(ath: &str, path: Rc<Path>, kern: &Mutex<Kern>) -> Result<Unit, KernErr> {
+        let grd = kern.lock();
+        Self::check(&grd.ram_store, ath, &path, false)?;
+
+        // inside encrypted unit
+        if let Some(acl) = grd.ram_store.enc_rule(&path) {
+            let u = grd.ram_store.load(Unit::path_share(Rc::new(acl.path.clone()))).ok_or(KernErr::DbLoadFault)?;
+            let u = Self::open(ath, u, &grd)?;
+
+            return u.find(path[acl.path.len()..].iter().map(|s| s.as_str())).ok_or(KernErr::DbLoadFault)
+        }
+
+        let mut u = grd.ram_store.view(ath, Unit::path_share(path.clone())).ok_or(KernErr::DbLoadFault)?;
+
+        // nested encrypted units, which caller can open
+        for acl in grd.ram_store.acl.iter().filter(|acl| acl.enc && acl.path.len() > path.len() && acl.path.starts_with(&path)) {
+            let rel = acl.path[path.len()..].iter().map(|s| s.as_str());
+
+            if let Some(_u) = u.clone().find(rel.clone()).and_then(|enc| Self::open(ath, enc, &grd).ok()) {
+                u = u.replace(rel, _u).ok_or(KernErr::DbLoadFault)?;
+            }
+        }
+        Ok(u)
+    }
+
+    fn open(ath: &str, enc: Unit, kern: &Kern) -> Result<Unit, KernErr> {
+        let enc = enc.as_str().ok_or(KernErr::DecryptFault)?;
+        kern.get_usr(ath)?.decrypt(&enc)
+    }
+
+    // encrypt unit to owner key, if it should be stored encrypted
+    fn seal(ath: &str, path: Rc<Path>, u: Unit, enc: bool, kern: &mut Kern) -> Result<Unit, KernErr> {
+        let was_enc = match kern.ram_store.enc_rule(&path) {
+            Some(acl) if acl.path.len() < path.len() => return Err(KernErr::DbSaveFault),
+            Some(_) => true,
+            None => false
+        };
+
+        if !enc && !was_enc {
+            return Ok(u)
+        }
+
+        let own = kern.ram_store.rule(&path).map(|acl| acl.own.clone()).unwrap_or(ath.into());
+
+        let old = match kern.ram_store.load(Unit::path_share(path)) {
+            Some(old) if was_enc => Some(Self::open(ath, old, kern)?),
+            old => old
+        };
+        let u = old.map(|old| old.merge_with(u.clone())).unwrap_or(u);
+
+        let own = kern.get_usr(&own)?;
+        let enc = own.encrypt(u, kern)?;
+
+        Ok(Unit::str(&enc))
+    }
+
+    // encrypt unit again to key of new owner
+    fn reseal(path: &[String], own: &str, kern: &mut Kern) -> Result<(), KernErr> {
+        let acl = match kern.ram_store.enc_rule(path) {
+            Some(acl) if acl.path.len() < path.len() => return Err(KernErr::DbAccessDenied),
+            Some(acl) => acl.clone(),
+            None => return Ok(())
+        };
+
+        let path = Rc::new(path.to_vec());
+
+        let enc = kern.ram_store.load(Unit::path_share(path.clone())).ok_or(KernErr::DbLoadFault)?;
+        let u = Self::open(&acl.own, enc, kern)?;
+
+        let own = kern.get_usr(own)?;
+        let enc = own.encrypt(u, kern)?;
+
+        kern.ram_store.save(Unit::path_share(path), Unit::str(&enc)).ok_or(KernErr::DbSaveFault)
+    }
+
+    // send writes of replicated units to known nodes, node signs them and tells author
+    fn replicate(ath: &str, paths: &[Rc<Path>], kern: &mut Kern) -> Result<(), KernErr> {
+        let paths = paths.iter().filter(|path| kern.ram_store.repl.covers(path)).cloned().collect::<Vec<_>>();
+
+        for path in paths {
+            let node = kern.drv.net.addr();
+            let ts = kern.ram_store.repl.write(&path, ath, node)?;
+            kern.ram_store.dirty = true;
+
+            let mut u = Vec::from([
+                (Unit::str("repl"), Unit::path_share(path.clone())),
+                (Unit::str("ts"), Unit::int_big(BigInt::from(ts))),
+                (Unit::str("usr"), Unit::str(ath))
+            ]);
+
+            // deleted unit has no value
+            if let Some(val) = kern.ram_store.load(Unit::path_share(path.clone())) {
+                u.push((Unit::str("val"), val));
+            }
+
+            let addrs = kern.peers.iter().map(|p| p.addr).collect::<Vec<_>>();
+
+            for addr in addrs {
+                let u = kern.new_unit(Unit::map(&u));
+                let msg = Msg::new(kern.get_node()?.clone(), u)?;
+
+                kern.net_push(addr, SERV_PATH.into(), msg)?;
+            }
+        }
+        Ok(())
+    }
+
     async fn get_size(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<usize> {
-        let (s, u, ath) = if let Some(s) = msg.clone().as_str() {
+        let (s, path, ath) = if let Some(s) = msg.clone().as_str() {
             // database
-            (s, kern.lock().ram_store.data.clone(), ath)
+            (s, None, ath)
         } else if let Some((u, path)) = msg.as_pair().into_iter().find_map(|(u0, u1)| Some((u0, u1.as_path()?))) {
             let (s, ath) = maybe!(as_async!(u, as_str, ath, orig, kern));
             // unit
-            (s, kern.lock().ram_store.load(Unit::path_share(path)).ok_or(KernErr::DbLoadFault)?, ath)
+            (s, Some(path), ath)
         } else {
             return Ok(None);
         };
@@ -93,6 +418,9 @@ impl StoreHlr {
             _ => return Ok(None)
         };
 
+        let path = path.unwrap_or_default();
+        let u = Self::view(&ath, path, kern)?;
+
         let size = u.size(units);
         Ok(Some((size, ath)))
     }
@@ -104,7 +432,7 @@ impl StoreHlr {
                 return Ok(None)
             }
 
-            let u = kern.lock().ram_store.data.clone();
+            let u = Self::view(&ath, Rc::new(Vec::new()), kern)?;
             return Ok(Some((u, ath)))
         }
 
@@ -113,7 +441,7 @@ impl StoreHlr {
         let (s, ath) = maybe!(as_async!(u, as_str, ath, orig, kern));
 
         if s.as_str() == "load" {
-            let u = kern.lock().ram_store.load(Unit::path_share(path)).ok_or(KernErr::DbLoadFault)?;
+            let u = Self::view(&ath, path, kern)?;
             return Ok(Some((u, ath)))
         }
         Ok(None)
@@ -121,11 +449,487 @@ impl StoreHlr {
     
     async fn save(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
         let (u, ath) = maybe!(as_map_find_async!(msg, "save", ath, orig, kern));
-        let path = maybe_ok!(msg.as_map_find("out").and_then(|u| u.as_path()));
+        let path = maybe_ok!(msg.clone().as_map_find("out").and_then(|u| u.as_path()));
+
+        let (enc, ath) = if let Some((enc, ath)) = as_map_find_as_async!(msg, "enc", as_bool, ath, orig, kern)? {
+            (enc, ath)
+        } else {
+            (false, ath)
+        };
+
+        let mut grd = kern.lock();
+        Self::check(&grd.ram_store, &ath, &path, true)?;
+
+        let u = Self::seal(&ath, path.clone(), u, enc, &mut grd)?;
+
+        grd.ram_store.save(Unit::path_share(path.clone()), u).ok_or(KernErr::DbSaveFault)?;
+        grd.ram_store.grant(&ath, &path);
+
+        if enc {
+            grd.ram_store.set_enc(&path);
+        }
+
+        Self::replicate(&ath, &[path], &mut grd)?;
+        grd.sync_store()?;
 
-        kern.lock().ram_store.save(Unit::path_share(path), u);
         Ok(Some(ath))
     }
+
+    async fn sync(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        // sync
+        if let Some(s) = msg.clone().as_str() {
+            return match s.as_str() {
+                "sync" | "flush" => {
+                    if ath.as_str() != RamStore::SUPER {
+                        return Err(KernErr::DbAccessDenied)
+                    }
+
+                    kern.lock().flush_store()?;
+                    Ok(Some(ath))
+                },
+                _ => Ok(None)
+            }
+        }
+
+        // (set.sync <bool>)
+        let (s, b) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "set.sync" {
+            return Ok(None)
+        }
+
+        let (b, ath) = maybe!(as_async!(b, as_bool, ath, orig, kern));
+
+        if ath.as_str() != RamStore::SUPER {
+            return Err(KernErr::DbAccessDenied)
+        }
+
+        let mut grd = kern.lock();
+        grd.ram_store.sync = b;
+
+        if b && grd.ram_store.dirty {
+            grd.flush_store()?;
+        }
+        Ok(Some(ath))
+    }
+
+    async fn path_com(com: &str, ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<Rc<Path>> {
+        // {<com>:@path}
+        if let Some(path) = msg.clone().as_map_find(com).and_then(|u| u.as_path()) {
+            return Ok(Some((path, ath)))
+        }
+
+        // (<com> @path)
+        let (s, path) = maybe_ok!(msg.as_pair());
+        let path = maybe_ok!(path.as_path());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != com {
+            return Ok(None)
+        }
+        Ok(Some((path, ath)))
+    }
+
+    async fn del(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let (path, ath) = maybe!(Self::path_com("del", ath, orig, msg, kern).await);
+
+        let mut grd = kern.lock();
+        Self::check(&grd.ram_store, &ath, &path, true)?;
+
+        grd.ram_store.remove(Unit::path_share(path.clone())).ok_or(KernErr::DbLoadFault)?;
+
+        Self::replicate(&ath, &[path], &mut grd)?;
+        grd.sync_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn keys(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<Vec<Unit>> {
+        // ls
+        if let Some(s) = msg.clone().as_str() {
+            return match s.as_str() {
+                "ls" | "keys" => {
+                    let u = Self::view(&ath, Rc::new(Vec::new()), kern)?;
+                    let keys = RamStore::keys_of(u).ok_or(KernErr::DbLoadFault)?;
+
+                    Ok(Some((keys, ath)))
+                },
+                _ => Ok(None)
+            }
+        }
+
+        // (ls @path)
+        let (path, ath) = if let Some(res) = Self::path_com("ls", ath.clone(), orig.clone(), msg.clone(), kern).await? {
+            res
+        } else {
+            maybe!(Self::path_com("keys", ath, orig, msg, kern).await)
+        };
+
+        // encrypted units are listed opened
+        let u = Self::view(&ath, path, kern)?;
+        let keys = RamStore::keys_of(u).ok_or(KernErr::DbLoadFault)?;
+
+        Ok(Some((keys, ath)))
+    }
+
+    async fn has(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<bool> {
+        let (path, ath) = maybe!(Self::path_com("has", ath, orig, msg, kern).await);
+
+        let has = kern.lock().ram_store.has(&ath, Unit::path_share(path.clone()));
+
+        // inside encrypted unit, only who can open it knows
+        let has = has || Self::view(&ath, path, kern).is_ok();
+        Ok(Some((has, ath)))
+    }
+
+    async fn mv(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let (from, to, ath) = if let Some((from, to)) = msg.clone().as_map_find("mv").and_then(|u| u.as_path()).and_then(|from| Some((from, msg.clone().as_map_find("out")?.as_path()?))) {
+            // {mv:@path out:@path}
+            (from, to, ath)
+        } else if let Some((s, paths)) = msg.as_pair() {
+            // (mv (@path @path))
+            let (from, to) = maybe_ok!(paths.as_pair().and_then(|(from, to)| Some((from.as_path()?, to.as_path()?))));
+            let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+            if s.as_str() != "mv" {
+                return Ok(None)
+            }
+            (from, to, ath)
+        } else {
+            return Ok(None)
+        };
+
+        let mut grd = kern.lock();
+        Self::check(&grd.ram_store, &ath, &from, true)?;
+        Self::check(&grd.ram_store, &ath, &to, true)?;
+
+        grd.ram_store.mv(Unit::path_share(from.clone()), Unit::path_share(to.clone())).ok_or(KernErr::DbSaveFault)?;
+
+        Self::replicate(&ath, &[from, to], &mut grd)?;
+        grd.sync_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn txn_op(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<StoreOp> {
+        // {save:unit out:@path}
+        if let Some(path) = msg.clone().as_map_find("out").and_then(|u| u.as_path()) {
+            if let Some((u, ath)) = as_map_find_async!(msg, "save", ath, orig, kern)? {
+                return Ok(Some((StoreOp::Save(u, path), ath)))
+            }
+
+            if let Some(from) = msg.clone().as_map_find("mv").and_then(|u| u.as_path()) {
+                return Ok(Some((StoreOp::Mv(from, path), ath)))
+            }
+        }
+
+        // {del:@path}
+        if let Some(path) = msg.clone().as_map_find("del").and_then(|u| u.as_path()) {
+            return Ok(Some((StoreOp::Del(path), ath)))
+        }
+
+        // (<op> <args>)
+        let (s, args) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        let op = match s.as_str() {
+            "save" => {
+                let (u, path) = maybe_ok!(args.as_pair());
+                let path = maybe_ok!(path.as_path());
+                let (u, ath) = maybe!(read_async!(u, ath, orig, kern));
+
+                return Ok(Some((StoreOp::Save(u, path), ath)))
+            },
+            "del" => StoreOp::Del(maybe_ok!(args.as_path())),
+            "mv" => {
+                let (from, to) = maybe_ok!(args.as_pair().and_then(|(from, to)| Some((from.as_path()?, to.as_path()?))));
+                StoreOp::Mv(from, to)
+            },
+            _ => return Ok(None)
+        };
+        Ok(Some((op, ath)))
+    }
+
+    async fn txn(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let (lst, mut ath) = if let Some(lst) = msg.clone().as_map_find("txn").and_then(|u| u.as_list()) {
+            // {txn:[op]}
+            (lst, ath)
+        } else if let Some((s, lst)) = msg.as_pair() {
+            // (txn [op])
+            let lst = maybe_ok!(lst.as_list());
+            let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+            if s.as_str() != "txn" {
+                return Ok(None)
+            }
+            (lst, ath)
+        } else {
+            return Ok(None)
+        };
+
+        // validate
+        let mut ops = Vec::with_capacity(lst.len());
+
+        for (i, op) in lst.iter().enumerate() {
+            let (op, _ath) = Self::txn_op(ath.clone(), orig.clone(), op.clone(), kern).await
+                .map_err(|e| KernErr::DbTxnFault(i, Box::new(e)))?
+                .ok_or(KernErr::DbTxnFault(i, Box::new(KernErr::ServErr(ServErr::NotValidUnit))))?;
+
+            ops.push(op);
+            ath = _ath;
+        }
+
+        // apply, rollback if some step fails
+        let mut grd = kern.lock();
+        let backup = grd.ram_store.clone();
+
+        let paths = ops.iter().flat_map(|op| match op {
+            StoreOp::Save(_, path) | StoreOp::Del(path) => Vec::from([path.clone()]),
+            StoreOp::Mv(from, to) => Vec::from([from.clone(), to.clone()])
+        }).collect::<Vec<_>>();
+
+        for (i, op) in ops.into_iter().enumerate() {
+            let res = match op {
+                StoreOp::Save(u, path) => Self::check(&grd.ram_store, &ath, &path, true).and_then(|_| {
+                    let u = Self::seal(&ath, path.clone(), u, false, &mut grd)?;
+
+                    grd.ram_store.save(Unit::path_share(path.clone()), u).ok_or(KernErr::DbSaveFault)?;
+                    grd.ram_store.grant(&ath, &path);
+                    Ok(())
+                }),
+                StoreOp::Del(path) => Self::check(&grd.ram_store, &ath, &path, true).and_then(|_| {
+                    grd.ram_store.remove(Unit::path_share(path)).ok_or(KernErr::DbLoadFault)
+                }),
+                StoreOp::Mv(from, to) => Self::check(&grd.ram_store, &ath, &from, true)
+                    .and_then(|_| Self::check(&grd.ram_store, &ath, &to, true))
+                    .and_then(|_| grd.ram_store.mv(Unit::path_share(from), Unit::path_share(to)).ok_or(KernErr::DbSaveFault))
+            };
+
+            if let Err(e) = res {
+                grd.ram_store = backup;
+                return Err(KernErr::DbTxnFault(i, Box::new(e)))
+            }
+        }
+
+        Self::replicate(&ath, &paths, &mut grd)?;
+        grd.sync_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn set_acl(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        // (set.mode (<mode> @path)) | (set.own (<usr> @path))
+        let (s, args) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if !["set.mode", "set.own"].contains(&s.as_str()) {
+            return Ok(None)
+        }
+
+        let (val, path) = maybe_ok!(args.as_pair());
+        let path = maybe_ok!(path.as_path());
+        let (val, ath) = maybe!(as_async!(val, as_str, ath, orig, kern));
+
+        let mut grd = kern.lock();
+
+        if !grd.ram_store.can_admin(&ath, &path) {
+            return Err(KernErr::DbAccessDenied)
+        }
+
+        if s.as_str() == "set.mode" {
+            let mode = maybe_ok!(StoreMode::parse(&val));
+            grd.ram_store.chmod(&path, mode);
+        } else {
+            Self::reseal(&path, &val, &mut grd)?;
+            grd.ram_store.chown(&path, &val);
+        }
+        grd.sync_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn get_acl(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        let (path, ath) = maybe!(Self::path_com("get.acl", ath, orig, msg, kern).await);
+
+        let grd = kern.lock();
+        Self::check(&grd.ram_store, &ath, &path, false)?;
+
+        let (own, mode) = match grd.ram_store.rule(&path) {
+            Some(acl) => (acl.own.clone(), acl.mode),
+            None => (RamStore::SUPER.into(), StoreMode::Ro)
+        };
+        let enc = grd.ram_store.enc_rule(&path).is_some();
+
+        let u = Unit::map(&[
+            (Unit::str("own"), Unit::str(&own)),
+            (Unit::str("mode"), Unit::str(mode.as_str())),
+            (Unit::str("enc"), Unit::bool(enc))
+        ]);
+        Ok(Some((u, ath)))
+    }
+
+    async fn repl(ath: Rc<String>, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        // {repl:@path ts:<uint> usr:<str> val:<unit>}, value is not read
+        let path = maybe_ok!(msg.clone().as_map_find("repl").and_then(|u| u.as_path()));
+        let ts = maybe_ok!(msg.clone().as_map_find("ts").and_then(|u| u.as_int_big()).and_then(|v| v.to_u64()));
+        let usr = maybe_ok!(msg.clone().as_map_find("usr").and_then(|u| u.as_str()));
+        let val = msg.as_map_find("val");
+
+        let mut grd = kern.lock();
+
+        // sent by known node, it runs as node user
+        let node = grd.peers.iter().find(|p| p.name == *ath).map(|p| p.addr).ok_or(KernErr::ServAccessDenied)?;
+
+        if !grd.ram_store.repl.covers(&path) {
+            return Err(KernErr::DbAccessDenied)
+        }
+
+        Self::check(&grd.ram_store, &usr, &path, true)?;
+
+        // older write
+        let ver = StoreVer {path: path.to_vec(), ts, usr: usr.to_string(), node};
+
+        let later = match grd.ram_store.repl.apply(ver)? {
+            Some(later) => later,
+            None => {
+                grd.ram_store.dirty = true;
+                grd.sync_store()?;
+
+                return Ok(Some(ath))
+            }
+        };
+
+        // later writes inside unit survive
+        let kept = later.into_iter().map(|p| {
+            let u = grd.ram_store.load(Unit::path(&p.iter().map(|s| s.as_str()).collect::<Vec<_>>()));
+            (p, u)
+        }).collect::<Vec<_>>();
+
+        grd.ram_store.remove(Unit::path_share(path.clone()));
+
+        if let Some(val) = val {
+            grd.ram_store.save(Unit::path_share(path.clone()), val).ok_or(KernErr::DbSaveFault)?;
+            grd.ram_store.grant(&usr, &path);
+        }
+
+        for (p, u) in kept {
+            let p = Unit::path(&p.iter().map(|s| s.as_str()).collect::<Vec<_>>());
+            grd.ram_store.remove(p.clone());
+
+            if let Some(u) = u {
+                grd.ram_store.save(p, u).ok_or(KernErr::DbSaveFault)?;
+            }
+        }
+
+        grd.ram_store.dirty = true;
+        grd.sync_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn set_repl(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        // (set.repl (@path <bool>))
+        let (s, args) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "set.repl" {
+            return Ok(None)
+        }
+
+        let (path, b) = maybe_ok!(args.as_pair());
+        let path = maybe_ok!(path.as_path());
+        let (b, ath) = maybe!(as_async!(b, as_bool, ath, orig, kern));
+
+        if ath.as_str() != RamStore::SUPER {
+            return Err(KernErr::DbAccessDenied)
+        }
+
+        let mut grd = kern.lock();
+        grd.ram_store.repl.paths.retain(|p| p.as_slice() != path.as_slice());
+
+        if b {
+            grd.ram_store.repl.paths.push(path.to_vec());
+        }
+
+        grd.ram_store.dirty = true;
+        grd.sync_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn get_repl(ath: Rc<String>, _orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        let s = maybe_ok!(msg.as_str());
+
+        if s.as_str() != "get.repl" {
+            return Ok(None)
+        }
+
+        let grd = kern.lock();
+        let repl = &grd.ram_store.repl;
+
+        let paths = repl.paths.iter().map(|p| Unit::path(&p.iter().map(|s| s.as_str()).collect::<Vec<_>>())).collect::<Vec<_>>();
+
+        // writes waiting for unreachable nodes
+        let peers = grd.peers.iter().map(|p| {
+            let que = grd.net_queue.iter().filter(|q| q.addr == p.addr && q.serv == SERV_PATH).count();
+
+            Unit::map(&[
+                (Unit::str("name"), Unit::str(&p.name)),
+                (Unit::str("que"), Unit::uint(que as u32))
+            ])
+        }).collect::<Vec<_>>();
+
+        let u = Unit::map(&[
+            (Unit::str("paths"), Unit::list(&paths)),
+            (Unit::str("clock"), Unit::int_big(BigInt::from(repl.clock))),
+            (Unit::str("ver"), Unit::uint(repl.ver.len() as u32)),
+            (Unit::str("peers"), Unit::list(&peers))
+        ]);
+        Ok(Some((u, ath)))
+    }
+
+    async fn conv(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        // (conv <fmt>)
+        let (s, fmt) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "conv" {
+            return Ok(None)
+        }
+
+        let (fmt, ath) = maybe!(as_async!(fmt, as_str, ath, orig, kern));
+
+        let fmt = match fmt.as_str() {
+            "txt" => StoreFmt::Text,
+            "bin" => StoreFmt::Bin,
+            _ => return Ok(None)
+        };
+
+        if ath.as_str() != RamStore::SUPER {
+            return Err(KernErr::DbAccessDenied)
+        }
+
+        let mut grd = kern.lock();
+        grd.ram_store.fmt = fmt;
+        grd.flush_store()?;
+
+        Ok(Some(ath))
+    }
+
+    async fn get_fmt(ath: Rc<String>, _orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        let s = maybe_ok!(msg.as_str());
+
+        if s.as_str() != "get.fmt" {
+            return Ok(None)
+        }
+
+        let fmt = match kern.lock().ram_store.fmt {
+            StoreFmt::Text => "txt",
+            StoreFmt::Bin => "bin"
+        };
+        Ok(Some((Unit::str(fmt), ath)))
+    }
 }
 
 
@@ -151,6 +955,107 @@ impl ServHlr for StoreHlr {
             return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
         }
 
+        // transaction
+        if let Some(_ath) = Self::txn(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // access control
+        if let Some((u, ath)) = Self::get_acl(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        if let Some(_ath) = Self::set_acl(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // replication
+        if let Some(_ath) = Self::repl(ath.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        if let Some((u, ath)) = Self::get_repl(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        if let Some(_ath) = Self::set_repl(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // list keys
+        if let Some((keys, ath)) = Self::keys(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), Unit::list(&keys))]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        // exists
+        if let Some((has, ath)) = Self::has(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), Unit::bool(has))]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        // delete
+        if let Some(_ath) = Self::del(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // move
+        if let Some(_ath) = Self::mv(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // get format
+        if let Some((u, ath)) = Self::get_fmt(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        // convert
+        if let Some(_ath) = Self::conv(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // sync
+        if let Some(_ath) = Self::sync(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
         // save
         if let Some(_ath) = Self::save(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
             if ath != _ath {
@@ -161,3 +1066,93 @@ impl ServHlr for StoreHlr {
         Ok(Some(msg))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use base64ct::{Base64, Encoding};
+
+    use crate::vnix::core::kern::KernErr;
+    use crate::vnix::core::unit::{Unit, UnitAs, UnitNew};
+    use crate::vnix::harness::{cluster, usr, Nodes, res_str};
+
+    #[test]
+    fn acl_commands() {
+        let kerns = cluster();
+        let [node1, _] = &kerns;
+        usr(node1, "alice");
+
+        let mut nodes = Nodes::new(&kerns);
+
+        // saved entry belongs to writer
+        nodes.run(node1, "node2", "{save:abc out:@txt.test}", "io.store").unwrap();
+
+        let res = nodes.run(node1, "alice", "(get.acl @txt.test)", "io.store");
+        assert_eq!(res_str(res), "{own:node2 mode:ro enc:f}");
+
+        // only owner changes rule
+        let res = nodes.run(node1, "alice", "(set.mode (prv @txt.test))", "io.store");
+        assert!(matches!(res, Err(KernErr::DbAccessDenied)));
+
+        nodes.run(node1, "node2", "(set.mode (prv @txt.test))", "io.store").unwrap();
+
+        let res = nodes.run(node1, "alice", "(get.acl @txt.test)", "io.store");
+        assert!(matches!(res, Err(KernErr::DbAccessDenied)));
+
+        // new owner reads private entry, old one doesn't
+        nodes.run(node1, "node2", "(set.own (alice @txt.test))", "io.store").unwrap();
+
+        let res = nodes.run(node1, "alice", "(get.acl @txt.test)", "io.store");
+        assert_eq!(res_str(res), "{own:alice mode:prv enc:f}");
+
+        let res = nodes.run(node1, "node2", "(load @txt.test)", "io.store");
+        assert!(matches!(res, Err(KernErr::DbAccessDenied)));
+
+        let res = nodes.run(node1, "alice", "(load @txt.test)", "io.store");
+        assert_eq!(res_str(res), "abc");
+    }
+
+    #[test]
+    fn save_enc() {
+        let kerns = cluster();
+        let [node1, _] = &kerns;
+        usr(node1, "alice");
+
+        let mut nodes = Nodes::new(&kerns);
+
+        nodes.run(node1, "node2", "{save:`top secret` out:@txt.sec enc:t}", "io.store").unwrap();
+
+        // stored as blob sealed to owner
+        let raw = node1.lock().ram_store.load(Unit::path(&["txt", "sec"])).and_then(|u| u.as_str()).unwrap();
+        let blob = Base64::decode_vec(&raw).unwrap();
+
+        assert!(!raw.contains("top secret"));
+        assert!(!blob.windows(10).any(|w| w == b"top secret"));
+
+        let res = nodes.run(node1, "node2", "(load @txt.sec)", "io.store");
+        assert_eq!(res_str(res), "`top secret`");
+
+        // readable entry, but other key doesn't open it
+        let res = nodes.run(node1, "alice", "(load @txt.sec)", "io.store");
+        assert!(matches!(res, Err(KernErr::DecryptFault)));
+    }
+
+    #[test]
+    fn txn_rollback() {
+        let kerns = cluster();
+        let [node1, _] = &kerns;
+        let mut nodes = Nodes::new(&kerns);
+
+        // kernel config is not writable by user
+        match nodes.run(node1, "node2", "(txn [(save (1 @txt.a)) (save (2 @sys.a)) (save (3 @txt.b))])", "io.store") {
+            Err(KernErr::DbTxnFault(1, e)) => assert!(matches!(*e, KernErr::DbAccessDenied)),
+            res => panic!("unexpected result {res:?}")
+        }
+
+        let grd = node1.lock();
+
+        for path in [["txt", "a"], ["sys", "a"], ["txt", "b"]] {
+            assert!(grd.ram_store.load(Unit::path(&path)).is_none());
+        }
+        assert!(grd.ram_store.rule(&["txt".into(), "a".into()]).is_none());
+    }
+}
@@ -163,6 +163,10 @@ impl Term {
                     TermKey::Esc => break,
                     _ => Yield::now().await
                 }
+            } else {
+                // wait for input without spinning
+                Kern::input_wait(kern).await;
+                continue;
             }
             Yield::now().await;
         }
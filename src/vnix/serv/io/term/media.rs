@@ -184,7 +184,7 @@ impl Media {
     
             // limit fps
             if elapsed < 900 / fps as usize {
-                let _ = kern.lock().drv.time.wait_async(Duration::Milli(900 / fps as usize - elapsed)).await;
+                let _ = Kern::sleep(kern, Duration::Milli(900 / fps as usize - elapsed)).await;
             }
         }
     
@@ -170,7 +170,7 @@ impl Text {
                     if let Some(key) = kern.lock().drv.cli.get_key(false).map_err(|e| KernErr::DrvErr(DrvErr::CLI(e)))? {
                         break key;
                     }
-                    Yield::now().await
+                    Kern::input_wait(kern).await
                 };
                 Ok(Some((key, ath)))
             },
@@ -248,7 +248,7 @@ pub const SERV_HELP: &'static str = "{
 pub struct CalcHlr;
 
 impl CalcHlr {
-    fn calc_single_op_int(op: &str, v: Int) -> Option<Int> {
+    async fn calc_single_op_int(op: &str, v: Int, kern: &Mutex<Kern>) -> Option<Int> {
         let res = match op {
             "neg" => -v.0.as_ref(),
             "abs" => num::abs(Rc::unwrap_or_clone(v.0)),
@@ -256,7 +256,15 @@ impl CalcHlr {
             "dec" => v.0.as_ref() - 1,
             "sqr" => v.0.as_ref() * v.0.as_ref(),
             "sqrt" => v.0.sqrt(),
-            "fac" => (1..=v.to_nat()?).fold(BigInt::from(1), |a, b| BigInt::from(a) * BigInt::from(b)),
+            "fac" => {
+                let mut res = BigInt::from(1);
+
+                for i in 1..=v.to_nat()? {
+                    res *= i;
+                    Kern::coop(kern).await;
+                }
+                res
+            },
             // "log" => libm::truncf(libm::logf(v as f32)) as i32,
             _ => return None
         };
@@ -311,7 +319,7 @@ impl CalcHlr {
                 let (op, ath) = maybe!(as_async!(op, as_str, ath, orig, kern));
                 let (val, ath) = maybe!(Self::op_int(ath.clone(), orig.clone(), val, kern).await);
     
-                return Ok(Self::calc_single_op_int(&op, val).map(|v| (v, ath)))
+                return Ok(Self::calc_single_op_int(&op, val, kern).await.map(|v| (v, ath)))
             }
     
             // {<op>:<val>}
@@ -319,7 +327,7 @@ impl CalcHlr {
             for op in ops {
                 if let Some((val, ath)) = as_map_find_async!(msg, op, ath, orig, kern)? {
                     let (val, ath) = maybe!(Self::op_int(ath.clone(), orig.clone(), val, kern).await);
-                    return Ok(Self::calc_single_op_int(&op, val).map(|v| (v, ath)))
+                    return Ok(Self::calc_single_op_int(&op, val, kern).await.map(|v| (v, ath)))
                 }
             }
             Ok(None)
@@ -1,3 +1,5 @@
 pub mod hw;
 pub mod usr;
+pub mod pol;
+pub mod net;
 pub mod task;
@@ -0,0 +1,332 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+
+use spin::Mutex;
+use async_trait::async_trait;
+
+use crate::vnix::utils::{Maybe, RamStore};
+use crate::{read_async, as_map_find_as_async, as_async, maybe, maybe_ok};
+
+use crate::vnix::core::msg::Msg;
+use crate::vnix::core::kern::{Kern, KernErr, Addr};
+use crate::vnix::core::peer::{Peer, PEERS_PATH, HIDDEN_PATH, EXPORTED_PATH, parse_remote_addr};
+use crate::vnix::core::serv::{ServHlr, ServInfo, ServResult};
+use crate::vnix::core::unit::{Unit, UnitNew, UnitAs, UnitReadAsyncI, UnitAsyncResult};
+
+
+pub const SERV_PATH: &'static str = "sys.net";
+
+pub const SERV_HELP: &'static str = "{
+    name:sys.net
+    info:`Network nodes registry and exported services`
+    tut:[
+        {
+            info:`Get address of this node`
+            com:get.addr@sys.net
+            res:`0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0001`
+        }
+        {
+            info:`Get all known nodes`
+            com:get@sys.net
+            res:[{name:laptop addr:`0xfe80:0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0002` key:`Ah2S2b..`}]
+        }
+        {
+            info:`Get known node`
+            com:(get laptop)@sys.net
+            res:{name:laptop addr:`0xfe80:0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0002` key:`Ah2S2b..`}
+        }
+        {
+            info:`Add node, only super can do it`
+            com:{add:laptop addr:`0xfe80:0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0002` key:`Ah2S2b..`}@sys.net
+        }
+        {
+            info:`Remove node, only super can do it`
+            com:(del laptop)@sys.net
+        }
+        {
+            info:`Send message to service on known node`
+            com:(sum [1 2])@math.calc:laptop
+            res:3
+        }
+        {
+            info:`Get services exported to other nodes`
+            com:get.exp@sys.net
+            res:[io.term math.calc dat.proc test.echo]
+        }
+        {
+            info:`Refuse to export service, only super can do it`
+            com:(set.exp (io.term f))@sys.net
+        }
+        {
+            info:`Get services exported by known node`
+            com:serv@sys.net:laptop
+            res:[io.term io.store math.calc sys.task]
+        }
+        {
+            info:`Get help of service on known node`
+            com:help.info@math.calc:laptop
+            res:`Mathematical calculation service`
+        }
+        {
+            info:`Get messages waiting for unreachable nodes`
+            com:get.que@sys.net
+            res:[{id:3 addr:`0xfe80:0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0002` serv:math.calc usr:alice tries:4}]
+        }
+        {
+            info:`Drop waiting message`
+            com:(drop 3)@sys.net
+        }
+        {
+            info:`Send message to node through relay node, that forwards it untouched`
+            com:(sum [1 2])@math.calc:relay>laptop
+            res:3
+        }
+        {
+            info:`Get public key of this node`
+            com:get.key@sys.net
+            res:`Ah2S2b..`
+        }
+    ]
+    man:{
+        get.addr:{
+            info:`Get address of this node`
+            schm:get.addr
+            tut:@tut.0
+        }
+        get.key:{
+            info:`Get public key of this node, other nodes add it with address to accept signed frames from this node`
+            schm:get.key
+            tut:@tut.13
+        }
+        get:{
+            info:`Get all known nodes or one node by name`
+            schm:[
+                get
+                (get name)
+            ]
+            tut:[@tut.1 @tut.2]
+        }
+        add:{
+            info:`Add node to registry with its node key, nodes are saved to @sys.net in storage and can be used as stream address; frames from unknown nodes or with wrong signature are dropped; node is also registered as user, its requests run as this user unless author is logged in here with same keys`
+            schm:{add:name addr:str key:str}
+            tut:[@tut.3 @tut.5]
+        }
+        del:{
+            info:`Remove node from registry`
+            schm:(del name)
+            tut:@tut.4
+        }
+        get.exp:{
+            info:`Get services, that other nodes can use; 'serv' message from other node also gets this list`
+            schm:get.exp
+            tut:[@tut.6 @tut.8 @tut.9]
+        }
+        get.que:{
+            info:`Get messages waiting for unreachable nodes; they are saved to disk and sent again with growing delay until node is reachable, message is dropped and its author gets error if no reply comes in 30 seconds, message without waiting author is dropped after about an hour`
+            schm:get.que
+            tut:@tut.10
+        }
+        drop:{
+            info:`Drop waiting message, only author or super can do it`
+            schm:(drop uint)
+            tut:@tut.11
+        }
+        route:{
+            info:`Stream address with relay nodes before destination node, destination checks original signature of message; node relays only to nodes known to it, route has at most 255 nodes`
+            schm:[
+                unit@serv:node>node
+                unit@serv:addr>addr
+            ]
+            tut:@tut.12
+        }
+        set.exp:{
+            info:`Export service or refuse to export it, saved to @sys.net in storage; all services except sys.* and io.store are exported by default`
+            schm:(set.exp (serv bool))
+            tut:@tut.7
+        }
+    }
+}";
+
+pub struct NetHlr;
+
+impl NetHlr {
+    async fn get(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        // get | get.addr | get.key | get.exp
+        if let Some(s) = msg.clone().as_str() {
+            let u = match s.as_str() {
+                "get" => Peer::list_as_unit(&kern.lock().peers),
+                "get.addr" => Unit::str(&Addr::Remote(kern.lock().drv.net.addr()).to_string()),
+                "get.key" => Unit::str(&kern.lock().get_node()?.pub_key),
+                "get.exp" => kern.lock().serv_list(true),
+                "get.que" => {
+                    let que = kern.lock().net_queue.iter()
+                        .filter(|p| ath.as_str() == RamStore::SUPER || p.ath == *ath)
+                        .map(|p| Unit::map(&[
+                            (Unit::str("id"), Unit::uint(p.id as u32)),
+                            (Unit::str("addr"), Unit::str(&Addr::Remote(p.addr).to_string())),
+                            (Unit::str("serv"), Unit::str(&p.serv)),
+                            (Unit::str("usr"), Unit::str(&p.ath)),
+                            (Unit::str("tries"), Unit::uint(p.tries))
+                        ]))
+                        .collect::<Vec<_>>();
+
+                    Unit::list(&que)
+                },
+                _ => return Ok(None)
+            };
+            return Ok(Some((u, ath)))
+        }
+
+        // (get <name>)
+        let (s, name) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "get" {
+            return Ok(None)
+        }
+
+        let (name, ath) = maybe!(as_async!(name, as_str, ath, orig, kern));
+
+        let u = kern.lock().get_peer(&name)?.as_unit();
+        Ok(Some((u, ath)))
+    }
+
+    async fn drop(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let (s, id) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "drop" {
+            return Ok(None)
+        }
+
+        let (id, ath) = maybe!(as_async!(id, as_uint, ath, orig, kern));
+
+        let mut grd = kern.lock();
+        let p = grd.net_queue.iter().find(|p| p.id == id as usize).ok_or(KernErr::NetMsgNotFound)?;
+
+        // only author or super can drop message
+        if ath.as_str() != RamStore::SUPER && p.ath != *ath {
+            return Err(KernErr::ServAccessDenied)
+        }
+
+        grd.net_drop(id as usize)?;
+        Ok(Some(ath))
+    }
+
+    async fn set(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let mut peers = kern.lock().peers.clone();
+        let mut hidden = kern.lock().net_hidden.clone();
+        let mut exported = kern.lock().net_exported.clone();
+
+        let ath = if let Some((name, ath)) = as_map_find_as_async!(msg, "add", as_str, ath, orig, kern)? {
+            // {add:<name> addr:<str> key:<str>}
+            let (addr, ath) = maybe!(as_map_find_as_async!(msg, "addr", as_str, ath, orig, kern));
+            let (key, ath) = maybe!(as_map_find_as_async!(msg, "key", as_str, ath, orig, kern));
+
+            if !Peer::valid_name(&name) {
+                return Err(KernErr::NodeFault)
+            }
+
+            let addr = parse_remote_addr(&addr).ok_or(KernErr::NodeFault)?;
+
+            if peers.iter().any(|p| p.name == *name) {
+                return Err(KernErr::NodeAlreadyReg)
+            }
+
+            peers.push(Peer {name: name.to_string(), addr, key: key.to_string()});
+            ath
+        } else if let Some((s, args)) = msg.as_pair() {
+            let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+            match s.as_str() {
+                // (del <name>)
+                "del" => {
+                    let (name, ath) = maybe!(as_async!(args, as_str, ath, orig, kern));
+
+                    if !peers.iter().any(|p| p.name == *name) {
+                        return Err(KernErr::NodeNotFound)
+                    }
+
+                    peers.retain(|p| p.name != *name);
+                    ath
+                },
+                // (set.exp (<serv> <bool>))
+                "set.exp" => {
+                    let (serv, exp) = maybe_ok!(args.as_pair());
+                    let (serv, ath) = maybe!(as_async!(serv, as_str, ath, orig, kern));
+                    let (exp, ath) = maybe!(as_async!(exp, as_bool, ath, orig, kern));
+
+                    hidden.retain(|s| *s != *serv);
+                    exported.retain(|s| *s != *serv);
+
+                    if exp {
+                        exported.push(serv.to_string());
+                    } else {
+                        hidden.push(serv.to_string());
+                    }
+                    ath
+                },
+                _ => return Ok(None)
+            }
+        } else {
+            return Ok(None)
+        };
+
+        if ath.as_str() != RamStore::SUPER {
+            return Err(KernErr::ServAccessDenied)
+        }
+
+        // save to storage
+        let mut grd = kern.lock();
+        grd.set_peers(peers.clone())?;
+
+        let names = |lst: &Vec<String>| Unit::list(&lst.iter().map(|s| Unit::str(s)).collect::<Vec<_>>());
+
+        for (path, u) in [(Unit::path(&PEERS_PATH), Peer::list_as_unit(&peers)), (Unit::path(&HIDDEN_PATH), names(&hidden)), (Unit::path(&EXPORTED_PATH), names(&exported))] {
+            grd.ram_store.remove(path.clone());
+            grd.ram_store.save(path, u).ok_or(KernErr::DbSaveFault)?;
+        }
+        grd.sync_store()?;
+
+        grd.net_hidden = hidden;
+        grd.net_exported = exported;
+
+        Ok(Some(ath))
+    }
+}
+
+#[async_trait(?Send)]
+impl ServHlr for NetHlr {
+    async fn hlr(&self, mut msg: Msg, _serv: ServInfo, kern: &Mutex<Kern>) -> ServResult {
+        let ath = Rc::new(msg.ath.clone());
+        let (_msg, ath) = maybe!(read_async!(msg.msg.clone(), ath.clone(), msg.msg.clone(), kern));
+
+        // get
+        if let Some((u, ath)) = Self::get(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        // drop
+        if let Some(_ath) = Self::drop(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        // add, del, set.exp
+        if let Some(_ath) = Self::set(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        Ok(Some(msg))
+    }
+}
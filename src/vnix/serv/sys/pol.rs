@@ -0,0 +1,181 @@
+use alloc::rc::Rc;
+use alloc::boxed::Box;
+use alloc::string::String;
+
+use spin::Mutex;
+use async_trait::async_trait;
+
+use crate::vnix::utils::{Maybe, RamStore};
+use crate::{read_async, as_map_find_async, as_async, maybe, maybe_ok};
+
+use crate::vnix::core::msg::Msg;
+use crate::vnix::core::kern::{Kern, KernErr};
+use crate::vnix::core::policy::{Policy, POLICY_PATH};
+use crate::vnix::core::serv::{ServHlr, ServInfo, ServResult};
+use crate::vnix::core::unit::{Unit, UnitNew, UnitAs, UnitReadAsyncI, UnitAsyncResult};
+
+
+pub const SERV_PATH: &'static str = "sys.pol";
+
+pub const SERV_HELP: &'static str = "{
+    name:sys.pol
+    info:`Service policies management`
+    tut:[
+        {
+            info:`Get policies of all services`
+            com:get@sys.pol
+            res:{
+                grp:{adm:[alice bob]}
+                serv:{
+                    io.term:[{com:`set.*` allow:[adm]}]
+                    sys.task:[{com:kill deny:[any]}]
+                }
+            }
+        }
+        {
+            info:`Get policy of service`
+            com:(get io.term)@sys.pol
+            res:[{com:`set.*` allow:[adm]}]
+        }
+        {
+            info:`Set policies of all services, only super can do it`
+            com:{
+                set:{
+                    grp:{adm:[alice bob]}
+                    serv:{
+                        io.term:[{com:`set.*` allow:[adm]}]
+                        sys.task:[{com:kill deny:[any]}]
+                    }
+                }
+            }@sys.pol
+        }
+        {
+            info:`Set policy of service, only super can do it`
+            com:(set (io.term [{com:`set.*` allow:[adm]} {com:cls deny:[guest]}]))@sys.pol
+        }
+    ]
+    man:{
+        get:{
+            info:`Get policies of all services or one service`
+            schm:[
+                get
+                (get serv)
+            ]
+            tut:[@tut.0 @tut.1]
+        }
+        set:{
+            info:`Set policies, they are saved to @sys.pol in storage and checked by kernel before message is handled; message, which command is not written literally or is encrypted, must be allowed by every rule of service`
+            rule:{
+                com:`command name, prefix like 'set.*' or '*' for any command`
+                allow:`users or groups, that can use command, 'any' for everyone`
+                deny:`users or groups, that cannot use command, 'any' for everyone`
+            }
+            schm:[
+                {set:{grp:{name:[usr]} serv:{serv:[rule]}}}
+                (set (serv [rule]))
+            ]
+            tut:[@tut.2 @tut.3]
+        }
+    }
+}";
+
+pub struct PolHlr;
+
+impl PolHlr {
+    async fn get(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        // get
+        if let Some(s) = msg.clone().as_str() {
+            if s.as_str() != "get" {
+                return Ok(None)
+            }
+
+            let u = kern.lock().policy.as_unit();
+            return Ok(Some((u, ath)))
+        }
+
+        // (get <serv>)
+        let (s, serv) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "get" {
+            return Ok(None)
+        }
+
+        let (serv, ath) = maybe!(as_async!(serv, as_str, ath, orig, kern));
+
+        let u = match kern.lock().policy.get_rules(&serv) {
+            Some(rules) => Policy::rules_as_unit(rules),
+            None => Unit::list(&[])
+        };
+        Ok(Some((u, ath)))
+    }
+
+    async fn set(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let (pol, ath) = if let Some((u, ath)) = as_map_find_async!(msg, "set", ath, orig, kern)? {
+            // {set:<policy>}
+            (maybe_ok!(Policy::from_unit(u)), ath)
+        } else if let Some((s, args)) = msg.as_pair() {
+            // (set (<serv> [<rule>]))
+            let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+            if s.as_str() != "set" {
+                return Ok(None)
+            }
+
+            let (serv, rules) = maybe_ok!(args.as_pair());
+            let (serv, ath) = maybe!(as_async!(serv, as_str, ath, orig, kern));
+            let (rules, ath) = maybe!(read_async!(rules, ath, orig, kern));
+
+            let rules = maybe_ok!(Policy::rules_from_unit(rules));
+
+            let mut pol = kern.lock().policy.clone();
+            pol.set_rules(&serv, rules);
+
+            (pol, ath)
+        } else {
+            return Ok(None)
+        };
+
+        if ath.as_str() != RamStore::SUPER {
+            return Err(KernErr::ServAccessDenied)
+        }
+
+        // save to storage
+        let mut grd = kern.lock();
+        let path = Unit::path(&POLICY_PATH);
+
+        grd.ram_store.remove(path.clone());
+        grd.ram_store.save(path, pol.as_unit()).ok_or(KernErr::DbSaveFault)?;
+        grd.sync_store()?;
+
+        grd.policy = pol;
+
+        Ok(Some(ath))
+    }
+}
+
+#[async_trait(?Send)]
+impl ServHlr for PolHlr {
+    async fn hlr(&self, mut msg: Msg, _serv: ServInfo, kern: &Mutex<Kern>) -> ServResult {
+        let ath = Rc::new(msg.ath.clone());
+        let (_msg, ath) = maybe!(read_async!(msg.msg.clone(), ath.clone(), msg.msg.clone(), kern));
+
+        // get
+        if let Some((u, ath)) = Self::get(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)]
+            );
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
+        // set
+        if let Some(_ath) = Self::set(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if ath != _ath {
+                msg = kern.lock().msg(&_ath.clone(), _msg)?;
+            }
+            return Ok(Some(msg))
+        }
+
+        Ok(Some(msg))
+    }
+}
@@ -6,20 +6,25 @@ use alloc::boxed::Box;
 use alloc::string::String;
 
 use spin::Mutex;
+use futures::future;
 use async_trait::async_trait;
 
-use crate::vnix::utils::Maybe;
+use crate::vnix::utils::{Maybe, RamStore};
 use crate::{read_async, as_map_find_async, maybe, as_map_find_as_async, as_async, maybe_ok, task_result};
 
 use crate::vnix::core::msg::Msg;
 use crate::vnix::core::kern::{Kern, KernErr};
-use crate::vnix::core::task::{Task, TaskRun, TaskSig, Yield};
+use crate::vnix::core::driver::{DrvErr, NetErr, Duration};
+use crate::vnix::core::task::{Task, TaskRun, TaskSig, TaskPrio, Yield};
 use crate::vnix::core::serv::{ServResult, ServHlr, ServInfo};
 use crate::vnix::core::unit::{Unit, UnitReadAsyncI, UnitModify, UnitAs, UnitNew, UnitAsyncResult, UnitTypeAsyncResult};
 
 
 pub const SERV_PATH: &'static str = "sys.task";
 
+// task placed on other node runs locally if no reply comes in time
+const RUN_ON_REPLY_TIMEOUT: Duration = Duration::Milli(30000);
+
 pub const SERV_HELP: &'static str = "{
     name:sys.task
     info:`Service for task management`
@@ -67,6 +72,7 @@ pub const SERV_HELP: &'static str = "{
                     name:unit.read
                     usr:super
                     par.id:36
+                    prio:norm
                 }
                 all:[
                     {
@@ -74,42 +80,49 @@ pub const SERV_HELP: &'static str = "{
                         name:init.load
                         usr:super
                         par.id:0
+                        prio:norm
                     }
                     {
                         id:9
                         name:unit.read
                         usr:super
                         par.id:0
+                        prio:norm
                     }
                     {
                         id:15
                         name:unit.read
                         usr:super
                         par.id:9
+                        prio:norm
                     }
                     {
                         id:32
                         name:unit.read
                         usr:super
                         par.id:15
+                        prio:norm
                     }
                     {
                         id:33
                         name:unit.read
                         usr:super
                         par.id:32
+                        prio:norm
                     }
                     {
                         id:36
                         name:unit.read
                         usr:super
                         par.id:33
+                        prio:norm
                     }
                     {
                         id:37
                         name:unit.read
                         usr:super
                         par.id:36
+                        prio:norm
                     }
                 ]
                 tree:{
@@ -170,6 +183,7 @@ pub const SERV_HELP: &'static str = "{
                 name:unit.read
                 usr:super
                 par.id:70
+                prio:norm
             }
         }
         {
@@ -181,42 +195,49 @@ pub const SERV_HELP: &'static str = "{
                     name:init.load
                     usr:super
                     par.id:0
+                    prio:norm
                 }
                 {
                     id:9
                     name:unit.read
                     usr:super
                     par.id:0
+                    prio:norm
                 }
                 {
                     id:15
                     name:unit.read
                     usr:super
                     par.id:9
+                    prio:norm
                 }
                 {
                     id:100
                     name:unit.read
                     usr:super
                     par.id:15
+                    prio:norm
                 }
                 {
                     id:101
                     name:unit.read
                     usr:super
                     par.id:100
+                    prio:norm
                 }
                 {
                     id:104
                     name:unit.read
                     usr:super
                     par.id:101
+                    prio:norm
                 }
                 {
                     id:105
                     name:unit.read
                     usr:super
                     par.id:104
+                    prio:norm
                 }
             ]
         }
@@ -276,11 +297,48 @@ pub const SERV_HELP: &'static str = "{
             info:`Kill task by id`
             com:(kill 2)@sys.task
         }
+        {
+            info:`Set task priority, interactive terminal tasks have high priority`
+            com:(set.prio (2 low))@sys.task
+        }
+        {
+            info:`Run several parallel tasks on known nodes and gather results`
+            com:{task.sim:[(sum [1 2])@math.calc (sum [3 4])@math.calc] on:[laptop pc]}@sys.task
+            res:[3 7]
+        }
+        {
+            info:`Run sequence of tasks on any known nodes and gather results`
+            com:{task.que:[(sum [1 2])@math.calc (sum [3 4])@math.calc] on:any}@sys.task
+            res:[3 7]
+        }
+        {
+            info:`Suspend task without losing its state and resume it`
+            com:[(stop 2)@sys.task (cont 2)@sys.task]
+        }
+        {
+            info:`Send user signal to task, loop task exits on 'int'`
+            com:[(int 2)@sys.task (sig (reload 2))@sys.task]
+        }
+        {
+            info:`Get count of task results, that are not claimed yet and dropped as expired`
+            com:get.res@sys.task
+            res:{cnt:2 drop:0}
+        }
+        {
+            info:`Run task with deadline in milliseconds, it is killed with its child tasks when deadline passes`
+            com:(task.timeout (500 (fac 100000)@math.calc))@sys.task
+            res:{err:timeout}
+        }
+        {
+            info:`Run parallel task with deadline and wait for its result`
+            com:{task.sep:(fac 100000)@math.calc timeout:1000}@sys.task
+            res:{err:timeout}
+        }
     ]
     man:{
         task:{
             loop:{
-                info:`Run loop task from stream`
+                info:`Run loop task from stream, it exits on 'int' signal`
                 schm:[
                     (task.loop stream)
                     (task.loop (uint stream))
@@ -290,10 +348,11 @@ pub const SERV_HELP: &'static str = "{
                 tut:[@tut.1 @tut.2]
             }
             sep:{
-                info:`Run parallel task`
+                info:`Run parallel task, its result is not kept`
                 schm:[
                     (task.sep stream)
                     {task.sep:stream}
+                    {task.sep:stream timeout:uint}
                 ]
                 tut:@tut.3
             }
@@ -307,16 +366,39 @@ pub const SERV_HELP: &'static str = "{
                 schm:[
                     (task.sim [unit@serv])
                     {task.sim:[unit@serv]}
+                    {task.sim:[unit@serv] on:[node]}
+                    {task.sim:[unit@serv] on:any}
+                    {task.sim:[unit@serv] timeout:uint}
                 ]
-                tut:@tut.5
+                tut:[@tut.5 @tut.14]
             }
             que:{
                 info:`Run sequence of tasks`
                 schm:[
                     (task.que [unit@serv])
                     {task.que:[unit@serv]}
+                    {task.que:[unit@serv] on:[node]}
+                    {task.que:[unit@serv] on:any}
                 ]
-                tut:@tut.6
+                tut:[@tut.6 @tut.15]
+            }
+            on:{
+                info:`Spread tasks over known nodes from sys.net by turns and gather results; task runs locally if node is unreachable or does not reply in 30 seconds; units that are not streams are read here`
+                schm:[
+                    on:[node]
+                    on:node
+                    on:any
+                ]
+                tut:[@tut.14 @tut.15]
+            }
+            timeout:{
+                info:`Run task with deadline in milliseconds from time driver; when it passes, task is killed with its child tasks and error unit is returned; 'timeout' key sets deadline of each parallel task, then results are gathered with error unit for each timed out task, also on other nodes`
+                schm:[
+                    (task.timeout (uint stream))
+                    {task.timeout:(uint stream)}
+                    timeout:uint
+                ]
+                tut:[@tut.19 @tut.20]
             }
             stk:{
                 info:`Create sequence of tasks with messages sended to service`
@@ -334,19 +416,45 @@ pub const SERV_HELP: &'static str = "{
                 get.run
                 get.all
                 get.tree
+                get.res
             ]
             tut:[
                 @tut.8
                 @tut.9
                 @tut.10
                 @tut.11
+                @tut.18
             ]
         }
         kill:{
-            info:`Kill task by id`
+            info:`Kill task by id with all its child tasks, task waiting for result gets error`
             schm:(kill uint)
             tut:@tut.12
         }
+        stop:{
+            info:`Suspend task, it keeps its state and is not run until continued`
+            schm:(stop uint)
+            tut:@tut.16
+        }
+        cont:{
+            info:`Resume suspended task`
+            schm:(cont uint)
+            tut:@tut.16
+        }
+        sig:{
+            info:`Send user signal to task, running task can check it; 'task.loop' exits on 'int'; only task owner or super can send any signal`
+            schm:[
+                (int uint)
+                (sig (str uint))
+            ]
+            tut:@tut.17
+        }
+        set.prio:{
+            info:`Set task priority, only task owner or super can do it; higher priority task runs first and longer before it is forced to yield; child tasks inherit priority`
+            schm:(set.prio (uint prio))
+            prio:[low norm high]
+            tut:@tut.13
+        }
     }
 }";
 
@@ -384,38 +492,59 @@ impl TaskHlr {
                 if let Some((_, _ath)) = read_async!(msg, ath, orig, kern)? {
                     ath = _ath;
                 }
+
+                if kern.lock().take_sig("int") {
+                    break;
+                }
             }
             return Ok(Some(ath))
         }
 
-        // infinite
+        // infinite, exits on `int` signal
         loop {
             read_async!(msg, ath, orig, kern)?;
+
+            if kern.lock().take_sig("int") {
+                return Ok(Some(ath))
+            }
         }
     }
 
-    async fn separate(mut ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
-        let msg = if let Some(msg) = msg.clone().as_map_find("task.sep") {
-            msg
-        } else if let Some((s, msg)) = msg.clone().as_pair() {
+    async fn separate(mut ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<Option<Unit>> {
+        let sep = if let Some(sep) = msg.clone().as_map_find("task.sep") {
+            sep
+        } else if let Some((s, sep)) = msg.clone().as_pair() {
             let (s, _ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
             ath = _ath;
 
             if s.as_str() != "task.sep" {
                 return Ok(None)
             }
-            msg
+            sep
         } else {
             return Ok(None)
         };
 
-        // infinite
-        if let Some((_msg, serv, _)) = msg.as_stream() {
-            let run = TaskRun(_msg, serv);
-            kern.lock().reg_task(&ath, "sys.task", run)?;
+        let (_msg, serv, _) = maybe_ok!(sep.as_stream());
+        let run = TaskRun(_msg, serv);
+
+        let dur = Self::deadline(ath.clone(), orig.clone(), msg, kern).await?;
+
+        // wait until deadline
+        if let Some(dur) = dur {
+            let id = {
+                let mut grd = kern.lock();
+                let id = grd.reg_task(&ath, "sys.task", run)?;
+
+                grd.task_timeout(id, dur)?;
+                id
+            };
+            return Ok(Some((Some(Self::result(id, kern).await?), ath)))
         }
-    
-        Ok(Some(ath))
+
+        // infinite
+        kern.lock().reg_task_detached(&ath, "sys.task", run)?;
+        Ok(Some((None, ath)))
     }
 
     async fn chain(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
@@ -442,10 +571,114 @@ impl TaskHlr {
         return Ok(Some((_msg, ath)))
     }
 
-    async fn queue(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+    // `on:any`, `on:node` or `on:[node]`
+    async fn nodes(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Vec<[u16; 8]>, KernErr> {
+        let (on, _) = maybe!(as_map_find_async!(msg, "on", ath, orig, kern));
+
+        let grd = kern.lock();
+
+        if on.clone().as_str().map_or(false, |s| s.as_str() == "any") {
+            let nodes = grd.peers.iter().map(|p| p.addr).collect::<Vec<_>>();
+
+            if nodes.is_empty() {
+                return Ok(Some(Vec::from([grd.drv.net.addr()])))
+            }
+            return Ok(Some(nodes))
+        }
+
+        if let Some(name) = on.clone().as_str() {
+            return Ok(Some(Vec::from([grd.get_peer(&name)?.addr])))
+        }
+
+        let lst = maybe_ok!(on.as_list());
+        let nodes = lst.iter().map(|u| {
+            let name = u.clone().as_str().ok_or(KernErr::NodeNotFound)?;
+            grd.get_peer(&name).map(|p| p.addr)
+        }).collect::<Result<Vec<_>, KernErr>>()?;
+
+        if nodes.is_empty() {
+            return Ok(None)
+        }
+        Ok(Some(nodes))
+    }
+
+    // `timeout:ms`, each task is killed with its subtree after it
+    async fn deadline(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Duration, KernErr> {
+        let (ms, _) = maybe!(as_map_find_as_async!(msg, "timeout", as_uint, ath, orig, kern));
+        Ok(Some(Duration::Milli(ms as usize)))
+    }
+
+    async fn timeout(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        let args = if let Some(args) = msg.clone().as_map_find("task.timeout") {
+            args
+        } else if let Some((s, args)) = msg.clone().as_pair() {
+            let (s, _) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+            if s.as_str() != "task.timeout" {
+                return Ok(None)
+            }
+            args
+        } else {
+            return Ok(None)
+        };
+
+        let (ms, stream) = maybe_ok!(args.as_pair());
+        let (ms, ath) = maybe!(as_async!(ms, as_uint, ath, orig, kern));
+        let (_msg, serv, _) = maybe_ok!(stream.as_stream());
+
+        let id = {
+            let mut grd = kern.lock();
+            let id = grd.reg_task(&ath, "sys.task", TaskRun(_msg, serv))?;
+
+            grd.task_timeout(id, Duration::Milli(ms as usize))?;
+            id
+        };
+
+        Ok(Some((Self::result(id, kern).await?, ath)))
+    }
+
+    // task killed by deadline gets `{err:timeout}`
+    async fn result(id: usize, kern: &Mutex<Kern>) -> Result<Unit, KernErr> {
+        match task_result!(id, kern) {
+            Ok(res) => Ok(res.and_then(|msg| msg.msg.as_map_find("msg")).unwrap_or(Unit::none())),
+            Err(KernErr::TaskTimeout) => Ok(Self::timeout_err()),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn timeout_err() -> Unit {
+        Unit::map(&[(Unit::str("err"), Unit::str("timeout"))])
+    }
+
+    // run task on node, locally if node is unreachable or doesn't reply in time
+    async fn run_on(ath: Rc<String>, msg: Unit, serv: String, addr: [u16; 8], dur: Option<Duration>, kern: &Mutex<Kern>) -> Result<Unit, KernErr> {
+        // remote
+        if addr != kern.lock().drv.net.addr() {
+            let _msg = kern.lock().msg(&ath, msg.clone())?;
+
+            match Kern::try_send_remote(kern, addr, serv.clone(), _msg, dur.unwrap_or(RUN_ON_REPLY_TIMEOUT)).await {
+                Err(KernErr::DrvErr(DrvErr::Net(NetErr::Timeout))) if dur.is_some() => return Ok(Self::timeout_err()),
+                Err(KernErr::DrvErr(DrvErr::Net(..))) => (),
+                res => return Ok(res?.and_then(|msg| msg.msg.as_map_find("msg")).unwrap_or(Unit::none()))
+            }
+        }
+
+        let id = {
+            let mut grd = kern.lock();
+            let id = grd.reg_task(&ath, "sys.task", TaskRun(msg, serv))?;
+
+            if let Some(dur) = dur {
+                grd.task_timeout(id, dur)?;
+            }
+            id
+        };
+        Self::result(id, kern).await
+    }
+
+    async fn queue(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitTypeAsyncResult<Option<Unit>> {
         let (lst, mut ath) = if let Some((lst, ath)) =  as_map_find_as_async!(msg, "task.que", as_list, ath, orig, kern)? {
             (lst, ath)
-        } else if let Some((s, lst)) = msg.as_pair() {
+        } else if let Some((s, lst)) = msg.clone().as_pair() {
             let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
 
             if s.as_str() != "task.que" {
@@ -458,18 +691,32 @@ impl TaskHlr {
             return Ok(None)
         };
 
+        // placement on nodes
+        if let Some(nodes) = Self::nodes(ath.clone(), orig.clone(), msg.clone(), kern).await? {
+            let mut res = Vec::new();
+
+            for (i, p) in lst.iter().enumerate() {
+                let u = match p.clone().as_stream() {
+                    Some((_msg, serv, _)) => Self::run_on(ath.clone(), _msg, serv, nodes[i % nodes.len()], None, kern).await?,
+                    None => maybe!(read_async!(p.clone(), ath, orig, kern)).0
+                };
+                res.push(u);
+            }
+            return Ok(Some((Some(Unit::list(&res)), ath)))
+        }
+
         for p in Rc::unwrap_or_clone(lst) {
             if let Some((_, _ath)) = read_async!(p, ath, orig, kern)? {
                 ath = _ath;
             }
         }
-        Ok(Some(ath))
+        Ok(Some((None, ath)))
     }
 
-    async fn sim(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<(), KernErr> {
+    async fn sim(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Unit, KernErr> {
         let lst = if let Some((lst, _)) =  as_map_find_as_async!(msg, "task.sim", as_list, ath, orig, kern)? {
             lst
-        } else if let Some((s, lst)) = msg.as_pair() {
+        } else if let Some((s, lst)) = msg.clone().as_pair() {
             let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
 
             if s.as_str() != "task.sim" {
@@ -482,10 +729,36 @@ impl TaskHlr {
             return Ok(None)
         };
 
+        let dur = Self::deadline(ath.clone(), orig.clone(), msg.clone(), kern).await?;
+
+        // placement on nodes, with deadline results are gathered on this node
+        let nodes = match Self::nodes(ath.clone(), orig.clone(), msg.clone(), kern).await? {
+            Some(nodes) => Some(nodes),
+            None if dur.is_some() => Some(Vec::from([kern.lock().drv.net.addr()])),
+            None => None
+        };
+
+        if let Some(nodes) = nodes {
+            // not stream is read here as in `task.que`
+            let runs = lst.iter().enumerate().map(|(i, p)| {
+                let (ath, orig, addr) = (ath.clone(), orig.clone(), nodes[i % nodes.len()]);
+
+                async move {
+                    match p.clone().as_stream() {
+                        Some((_msg, serv, _)) => Self::run_on(ath, _msg, serv, addr, dur, kern).await,
+                        None => Ok(read_async!(p, ath, orig, kern)?.map_or(Unit::none(), |(u, _)| u))
+                    }
+                }
+            });
+
+            let res = future::join_all(runs).await.into_iter().collect::<Result<Vec<_>, KernErr>>()?;
+            return Ok(Some(Unit::list(&res)))
+        }
+
         for p in lst.iter() {
             if let Some((_msg, serv, _)) = p.clone().as_stream() {
                 let run = TaskRun(_msg, serv);
-                kern.lock().reg_task(&ath, "sys.task", run)?;
+                kern.lock().reg_task_detached(&ath, "sys.task", run)?;
             }
         }
         Ok(None)
@@ -532,7 +805,14 @@ impl TaskHlr {
         }
 
         // separate
-        if let Some(_ath) = Self::separate(ath.clone(), msg.clone(), orig.clone(), kern).await? {
+        if let Some((u, _ath)) = Self::separate(ath.clone(), msg.clone(), orig.clone(), kern).await? {
+            if let Some(u) = u {
+                let msg = Unit::map(&[
+                    (Unit::str("msg"), u)]
+                );
+                return Ok(Some((Some(msg), _ath)))
+            }
+
             if _ath != ath {
                 return Ok(Some((Some(msg), ath)))
             }
@@ -548,10 +828,22 @@ impl TaskHlr {
         }
     
         // sim
-        Self::sim(ath.clone(), msg.clone(), orig.clone(), kern).await?;
+        if let Some(u) = Self::sim(ath.clone(), msg.clone(), orig.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)]
+            );
+            return Ok(Some((Some(msg), ath)))
+        }
     
         // queue
-        if let Some(_ath) = Self::queue(ath.clone(), msg.clone(), orig.clone(), kern).await? {
+        if let Some((u, _ath)) = Self::queue(ath.clone(), msg.clone(), orig.clone(), kern).await? {
+            if let Some(u) = u {
+                let msg = Unit::map(&[
+                    (Unit::str("msg"), u)]
+                );
+                return Ok(Some((Some(msg), _ath)))
+            }
+
             if _ath != ath {
                 return Ok(Some((Some(msg), ath)))
             }
@@ -566,6 +858,14 @@ impl TaskHlr {
             return Ok(Some((None, ath)))
         }
 
+        // timeout
+        if let Some((msg, ath)) = Self::timeout(ath.clone(), msg.clone(), orig.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), msg)]
+            );
+            return Ok(Some((Some(msg), ath)))
+        }
+
         // stream
         if let Some((msg, ath)) = Self::stream(ath.clone(), msg.clone(), orig.clone(), kern).await? {
             let msg = Unit::map(&[
@@ -583,13 +883,15 @@ impl TaskHlr {
         let info = {
             let task = maybe_ok!(kern.lock().get_task_running());
             let tasks = kern.lock().get_tasks_running();
+            let (res_cnt, res_drop) = kern.lock().task_result_stat();
 
             let task_lst = tasks.iter().map(|t| {
                 Unit::map(&[
                     (Unit::str("id"), Unit::uint(t.id as u32)),
                     (Unit::str("name"), Unit::str(&t.name)),
                     (Unit::str("usr"), Unit::str(&t.usr)),
-                    (Unit::str("par.id"), Unit::uint(t.parent_id as u32))
+                    (Unit::str("par.id"), Unit::uint(t.parent_id as u32)),
+                    (Unit::str("prio"), Unit::str(t.prio.as_str()))
                 ])
             }).collect();
 
@@ -630,11 +932,19 @@ impl TaskHlr {
                         (Unit::str("id"), Unit::uint(task.id as u32)),
                         (Unit::str("name"), Unit::str(&task.name)),
                         (Unit::str("usr"), Unit::str(&task.usr)),
-                        (Unit::str("par.id"), Unit::uint(task.parent_id as u32))
+                        (Unit::str("par.id"), Unit::uint(task.parent_id as u32)),
+                        (Unit::str("prio"), Unit::str(task.prio.as_str()))
                     ])
                 ),
                 (Unit::str("all"), Unit::list_share(Rc::new(task_lst))),
-                (Unit::str("tree"), task_tree)
+                (Unit::str("tree"), task_tree),
+                (
+                    Unit::str("res"),
+                    Unit::map(&[
+                        (Unit::str("cnt"), Unit::uint(res_cnt as u32)),
+                        (Unit::str("drop"), Unit::uint(res_drop as u32))
+                    ])
+                )
             ])
         };
         Yield::now().await;
@@ -645,22 +955,70 @@ impl TaskHlr {
             "get.run" => maybe_ok!(info.find(["run"].into_iter())),
             "get.all" => maybe_ok!(info.find(["all"].into_iter())),
             "get.tree" => maybe_ok!(info.find(["tree"].into_iter())),
+            "get.res" => maybe_ok!(info.find(["res"].into_iter())),
             _ => return Ok(None)
         };
         Ok(Some((res, ath)))
     }
 
     async fn signal(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
-        let (sig, id) = maybe_ok!(msg.as_pair());
+        let (s, args) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        // (sig (<str> <uint>))
+        let (sig, id, ath) = if s.as_str() == "sig" {
+            let (sig, id) = maybe_ok!(args.as_pair());
+            let (sig, ath) = maybe!(as_async!(sig, as_str, ath, orig, kern));
+
+            (TaskSig::Usr(sig.to_string()), id, ath)
+        } else {
+            let sig = match s.as_str() {
+                "kill" => TaskSig::Kill,
+                "stop" => TaskSig::Stop,
+                "cont" => TaskSig::Cont,
+                "int" => TaskSig::Usr("int".into()),
+                _ => return Ok(None)
+            };
+            (sig, args, ath)
+        };
 
-        let (sig, ath) = maybe!(as_async!(sig, as_str, ath, orig, kern));
         let (id, ath) = maybe!(as_async!(id, as_uint, ath, orig, kern));
 
-        match sig.as_str() {
-            "kill" => kern.lock().task_sig(id as usize, TaskSig::Kill)?,
-            _ => return Ok(None)
+        let mut grd = kern.lock();
+        let task = grd.get_task(id as usize).ok_or(KernErr::TaskNotFound)?;
+
+        // only owner or super can send signal
+        if ath.as_str() != RamStore::SUPER && task.usr != *ath {
+            return Err(KernErr::ServAccessDenied)
         }
 
+        grd.task_sig(id as usize, sig)?;
+        Ok(Some(ath))
+    }
+
+    async fn prio(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> Maybe<Rc<String>, KernErr> {
+        let (s, args) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "set.prio" {
+            return Ok(None)
+        }
+
+        let (id, prio) = maybe_ok!(args.as_pair());
+        let (id, ath) = maybe!(as_async!(id, as_uint, ath, orig, kern));
+        let (prio, ath) = maybe!(as_async!(prio, as_str, ath, orig, kern));
+
+        let prio = maybe_ok!(TaskPrio::from_str(&prio));
+
+        let mut grd = kern.lock();
+        let task = grd.get_tasks_running().into_iter().find(|t| t.id == id as usize).ok_or(KernErr::TaskNotFound)?;
+
+        // only owner or super can change priority
+        if ath.as_str() != RamStore::SUPER && task.usr != *ath {
+            return Err(KernErr::ServAccessDenied)
+        }
+
+        grd.task_prio(id as usize, prio)?;
         Ok(Some(ath))
     }
 }
@@ -686,6 +1044,15 @@ impl ServHlr for TaskHlr {
             return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
         }
 
+        // priority
+        if let Some(_ath) = Self::prio(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
+            if _ath != ath {
+                ath = _ath;
+                msg = kern.lock().msg(&ath, _msg.clone())?;
+            }
+            return Ok(Some(msg))
+        }
+
         // signal
         if let Some(_ath) = Self::signal(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
             if _ath != ath {
@@ -8,7 +8,7 @@ use spin::Mutex;
 use async_trait::async_trait;
 
 use crate::vnix::utils::Maybe;
-use crate::{as_async, as_map_find_as_async, maybe};
+use crate::{as_async, as_map_find_as_async, maybe, maybe_ok};
 
 use crate::vnix::core::msg::Msg;
 use crate::vnix::core::user::Usr;
@@ -16,7 +16,7 @@ use crate::vnix::core::task::Yield;
 use crate::vnix::core::kern::{Kern, KernErr};
 use crate::vnix::core::driver::{DrvErr, CLIErr};
 use crate::vnix::core::serv::{ServHlr, ServInfo, ServResult};
-use crate::vnix::core::unit::{Unit, UnitReadAsyncI, UnitNew, UnitAs, UnitParse};
+use crate::vnix::core::unit::{Unit, UnitReadAsyncI, UnitNew, UnitAs, UnitParse, UnitAsyncResult};
 
 
 pub const SERV_PATH: &'static str = "sys.usr";
@@ -35,6 +35,8 @@ pub const SERV_HELP: &'static str = "{
                 ath:test
                 pub:`AiOte6qwiIcJTWzLjAyA+d6pwVs4eRTi7fEqdDFy2a6z`
                 priv:`AYi2fBh4vQ/aQR2qU78XlTsx3huL0dIGzIsRHKYB+ls=`
+                pub.enc:`A3fZKmbAcNHSbU5Xqvb9EPrIY8Q8douUhJUmiEekwBLc`
+                priv.enc:`GpHLjsSxq8HpzJ+QtbCklxj+tPQbXo942DSAyg/VLyo=`
             }
         }
         {
@@ -42,10 +44,12 @@ pub const SERV_HELP: &'static str = "{
             com:{
                 ath:test
                 pub:`AiOte6qwiIcJTWzLjAyA+d6pwVs4eRTi7fEqdDFy2a6z`
+                pub.enc:`A3fZKmbAcNHSbU5Xqvb9EPrIY8Q8douUhJUmiEekwBLc`
             }@sys.usr
             res:{
                 ath:test
                 pub:`AiOte6qwiIcJTWzLjAyA+d6pwVs4eRTi7fEqdDFy2a6z`
+                pub.enc:`A3fZKmbAcNHSbU5Xqvb9EPrIY8Q8douUhJUmiEekwBLc`
                 priv:-
             }
         }
@@ -55,13 +59,21 @@ pub const SERV_HELP: &'static str = "{
                 ath:test
                 pub:`AiOte6qwiIcJTWzLjAyA+d6pwVs4eRTi7fEqdDFy2a6z`
                 priv:`AYi2fBh4vQ/aQR2qU78XlTsx3huL0dIGzIsRHKYB+ls=`
+                pub.enc:`A3fZKmbAcNHSbU5Xqvb9EPrIY8Q8douUhJUmiEekwBLc`
+                priv.enc:`GpHLjsSxq8HpzJ+QtbCklxj+tPQbXo942DSAyg/VLyo=`
             }@sys.usr
             res:{
                 ath:test
                 pub:`AiOte6qwiIcJTWzLjAyA+d6pwVs4eRTi7fEqdDFy2a6z`
                 priv:`AYi2fBh4vQ/aQR2qU78XlTsx3huL0dIGzIsRHKYB+ls=`
+                pub.enc:`A3fZKmbAcNHSbU5Xqvb9EPrIY8Q8douUhJUmiEekwBLc`
+                priv.enc:`GpHLjsSxq8HpzJ+QtbCklxj+tPQbXo942DSAyg/VLyo=`
             }
         }
+        {
+            info:`Send message encrypted for 'test' user, only service on node where 'test' is logged in can open it`
+            com:(send.enc (test abc@test.dump))@sys.usr
+        }
     ]
     man:-
 }";
@@ -78,23 +90,64 @@ impl UsrHlr {
         let (_ath, ath) = maybe!(as_map_find_as_async!(msg, "ath", as_str, ath, orig, kern));
 
         if let Some((pub_key, _)) = as_map_find_as_async!(msg, "pub", as_str, ath, orig, kern)? {
+            // accounts without encryption keys use signing keys
+            let enc_pub_key = as_map_find_as_async!(msg, "pub.enc", as_str, ath, orig, kern)?.map(|(k, _)| k).filter(|k| !k.is_empty()).unwrap_or(pub_key.clone());
+
             if let Some((priv_key, _)) = as_map_find_as_async!(msg, "priv", as_str, ath, orig, kern)? {
-                // {ath:test pub:.. priv:..}
-                return Ok(Some((Usr::login(&_ath, &priv_key, &pub_key)?, None)))
+                let enc_priv_key = as_map_find_as_async!(msg, "priv.enc", as_str, ath, orig, kern)?.map(|(k, _)| k).filter(|k| !k.is_empty()).unwrap_or(priv_key.clone());
+
+                // {ath:test pub:.. priv:.. pub.enc:.. priv.enc:..}
+                return Ok(Some((Usr::login(&_ath, &priv_key, &pub_key, &enc_priv_key, &enc_pub_key)?, None)))
             }
 
-            // {ath:test pub:..}
-            return Ok(Some((Usr::guest(&_ath, &pub_key)?, None)))
+            // {ath:test pub:.. pub.enc:..}
+            return Ok(Some((Usr::guest(&_ath, &pub_key, &enc_pub_key)?, None)))
         }
 
         // {ath:test}
         return Usr::new(&_ath, &mut kern.lock()).map(|(usr, out)| Some((usr, Some(out))))
     }
+
+    async fn send_enc(ath: Rc<String>, orig: Unit, msg: Unit, kern: &Mutex<Kern>) -> UnitAsyncResult {
+        // (send.enc (<usr> unit@serv))
+        let (s, args) = maybe_ok!(msg.as_pair());
+        let (s, ath) = maybe!(as_async!(s, as_str, ath, orig, kern));
+
+        if s.as_str() != "send.enc" {
+            return Ok(None)
+        }
+
+        let (to, stream) = maybe_ok!(args.as_pair());
+        let (to, ath) = maybe!(as_async!(to, as_str, ath, orig, kern));
+
+        // stream message is encrypted as is, not read
+        let (u, serv, addr) = maybe_ok!(stream.as_stream());
+
+        let route = kern.lock().resolve(&addr)?;
+        let msg = kern.lock().msg_enc(&ath, &to, u)?;
+
+        let res = if route.is_empty() {
+            maybe!(Kern::send(kern, serv, msg).await)
+        } else {
+            maybe!(Kern::send_remote(kern, route, serv, msg).await)
+        };
+
+        // remote reply is signed by user of other node, result belongs to sender
+        let u = maybe_ok!(res.msg.as_map_find("msg"));
+        Ok(Some((u, ath)))
+    }
 }
 
 #[async_trait(?Send)]
 impl ServHlr for UsrHlr {
     async fn hlr(&self, msg: Msg, _serv: ServInfo, kern: &Mutex<Kern>) -> ServResult {
+        if let Some((u, ath)) = Self::send_enc(Rc::new(msg.ath.clone()), msg.msg.clone(), msg.msg.clone(), kern).await? {
+            let msg = Unit::map(&[
+                (Unit::str("msg"), u)
+            ]);
+            return kern.lock().msg(&ath, msg).map(|msg| Some(msg))
+        }
+
         if let Some((usr, out)) = Self::auth(Rc::new(msg.ath.clone()), msg.msg.clone(), msg.msg.clone(), kern).await? {
             kern.lock().reg_usr(usr.clone())?;
             writeln!(kern.lock(), "INFO vnix:sys.usr: user `{}` registered", usr).map_err(|_| KernErr::DrvErr(DrvErr::CLI(CLIErr::Write)))?;
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use spin::Mutex;
 
 use async_trait::async_trait;
@@ -14,17 +16,31 @@ pub const SERV_PATH: &'static str = "test.dump";
 pub const SERV_HELP: &'static str = "{
     name:test.dump
     info:`Dump message to unit service`
-    tut:{
-        info:`Dump message`
-        com:abc@test.dump
-        res:{
-            ath:super
-            size:35
-            msg:abc
-            hash:`tTqmP8E+h8YCupEBG9NA9tIQTCUtEBczPpE9jOTthDI=`
-            sign:`M3VaF3AedSnx+/KNXOx2AXIn+8p+nVilbDo68X3dd5d9qMvlXTpSW6FMgw//fPErtg9r7YBcSZFz2i+nCFb0aQ==`
+    tut:[
+        {
+            info:`Dump message`
+            com:abc@test.dump
+            res:{
+                ath:super
+                size:35
+                msg:abc
+                hash:`tTqmP8E+h8YCupEBG9NA9tIQTCUtEBczPpE9jOTthDI=`
+                sign:`M3VaF3AedSnx+/KNXOx2AXIn+8p+nVilbDo68X3dd5d9qMvlXTpSW6FMgw//fPErtg9r7YBcSZFz2i+nCFb0aQ==`
+            }
         }
-    }
+        {
+            info:`Dump message encrypted for 'test' user, service opens it by recipient key if 'test' is logged in on this node`
+            com:(send.enc (test abc@test.dump))@sys.usr
+            res:{
+                ath:super
+                to:test
+                size:..
+                msg:abc
+                hash:..
+                sign:..
+            }
+        }
+    ]
     man:-
 }";
 
@@ -34,13 +50,17 @@ pub struct DumpHlr;
 #[async_trait(?Send)]
 impl ServHlr for DumpHlr {
     async fn hlr(&self, msg: Msg, _serv: ServInfo, kern: &Mutex<Kern>) -> ServResult {
+        // encrypted message stays sealed if recipient has no private key here
+        let u = kern.lock().open_msg(&msg).unwrap_or(msg.msg.clone());
+
         let u = Unit::map(&[
-            (Unit::str("ath"), Unit::str(&msg.ath)),
-            (Unit::str("size"), Unit::uint(msg.size as u32)),
-            (Unit::str("msg"), msg.msg.clone()),
-            (Unit::str("hash"), Unit::str(&msg.hash)),
-            (Unit::str("sign"), Unit::str(&msg.sign)),
-        ]);
+            Some((Unit::str("ath"), Unit::str(&msg.ath))),
+            msg.to.as_ref().map(|to| (Unit::str("to"), Unit::str(to))),
+            Some((Unit::str("size"), Unit::uint(msg.size as u32))),
+            Some((Unit::str("msg"), u)),
+            Some((Unit::str("hash"), Unit::str(&msg.hash))),
+            Some((Unit::str("sign"), Unit::str(&msg.sign))),
+        ].into_iter().flatten().collect::<Vec<_>>());
 
         let _msg = Unit::map(&[
             (Unit::str("msg"), u)
@@ -5,7 +5,7 @@ use alloc::string::String;
 use spin::Mutex;
 use async_trait::async_trait;
 
-use crate::vnix::core::driver::{DrvErr, Duration, Time, TimeUnit};
+use crate::vnix::core::driver::{DrvErr, Duration, TimeUnit};
 
 use crate::vnix::core::task::Yield;
 use crate::{as_async, as_map_find_as_async, maybe, maybe_ok, read_async};
@@ -174,13 +174,7 @@ impl ServHlr for ChronoHlr {
 
         // wait
         if let Some((dur, _ath)) = Self::wait(ath.clone(), _msg.clone(), _msg.clone(), kern).await? {
-            let time = unsafe {
-                let lck = kern.lock();
-                let time = &lck.drv.time as *const Box<dyn Time>;
-                &*time
-            };
-
-            time.wait_async(dur).await.map_err(|e| KernErr::DrvErr(DrvErr::Time(e)))?;
+            Kern::sleep(kern, dur).await?;
 
             if ath != _ath {
                 msg = kern.lock().msg(&_ath.clone(), msg.msg)?;
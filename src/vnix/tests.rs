@@ -0,0 +1,268 @@
+use alloc::vec::Vec;
+use alloc::format;
+use alloc::string::String;
+
+use spin::Mutex;
+
+use super::harness::{cluster, apart, Nodes, res_str, load};
+use super::core::driver::{DrvErr, NetErr};
+use super::core::wire::{NetPending, NET_QUEUE_NAME};
+use super::core::kern::{Kern, KernErr, Addr};
+use super::core::unit::{Unit, UnitParse, UnitParseErr, UnitAs};
+
+
+#[test]
+fn remote_stream() {
+    let kerns = cluster();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    // `super` of other node has different keys, so request runs as `node1` there
+    let res = nodes.run(node1, "super", "(neg (sum [1 2])@math.calc:node2)", "math.calc");
+
+    assert_eq!(res.as_ref().unwrap().as_ref().unwrap().ath, "super");
+    assert_eq!(res_str(res), "-3");
+}
+
+#[test]
+fn remote_stream_unknown_node() {
+    let kerns = cluster();
+    let [node1, node2] = &kerns;
+    node2.lock().set_peers(Vec::new()).unwrap();
+
+    let mut nodes = Nodes::new(&kerns);
+
+    // frames of unknown node are dropped, so no reply comes
+    let res = nodes.run(node1, "super", "(task.timeout (100 (neg (sum [1 2])@math.calc:node2)@math.calc))", "sys.task");
+    assert_eq!(res_str(res), "{err:timeout}");
+}
+
+#[test]
+fn replication_converges() {
+    let kerns = cluster();
+    let [node1, node2] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    for kern in [node1, node2] {
+        nodes.run(kern, "super", "(set.repl (@shared t))", "io.store").unwrap();
+    }
+
+    // write is sent by node for its author
+    nodes.run(node1, "super", "{save:abc out:@shared.a}", "io.store").unwrap();
+    nodes.settle();
+
+    assert_eq!(load(node2, "@shared.a").as_deref(), Some("abc"));
+
+    // concurrent writes with same timestamp and author, both nodes pick the same one
+    nodes.task(node1, "super", "{save:x out:@shared.b}", "io.store");
+    nodes.task(node2, "super", "{save:y out:@shared.b}", "io.store");
+    nodes.settle();
+
+    assert!(load(node1, "@shared.b").is_some());
+    assert_eq!(load(node1, "@shared.b"), load(node2, "@shared.b"));
+
+    // later write inside unit is kept when earlier write of whole unit comes
+    nodes.run(node2, "super", "{save:{c:1 d:2} out:@shared.a}", "io.store").unwrap();
+    nodes.settle();
+
+    let clock = |kern: &Mutex<Kern>| kern.lock().ram_store.repl.clock;
+
+    while clock(node1) <= clock(node2) + 1 {
+        node1.lock().ram_store.repl.write(&[String::from("tmp")], "super", [0; 8]).unwrap();
+    }
+
+    nodes.task(node1, "super", "{save:3 out:@shared.a.c}", "io.store");
+    nodes.task(node2, "super", "{save:{c:5 d:6} out:@shared.a}", "io.store");
+    nodes.settle();
+
+    assert_eq!(load(node1, "@shared.a"), load(node2, "@shared.a"));
+    assert_eq!(load(node2, "@shared.a.c").as_deref(), Some("3"));
+    assert_eq!(load(node2, "@shared.a.d").as_deref(), Some("6"));
+}
+
+#[test]
+fn replication_from_user_denied() {
+    let kerns = cluster();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    nodes.run(node1, "super", "(set.repl (@shared t))", "io.store").unwrap();
+
+    // only known node can send writes
+    let res = nodes.run(node1, "super", "{repl:@shared.a ts:1 usr:super val:abc}", "io.store");
+    assert!(matches!(res, Err(KernErr::ServAccessDenied)));
+}
+
+#[test]
+fn private_services_not_exported() {
+    let kerns = cluster();
+    let [node1, node2] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    for serv in ["sys.usr", "sys.task", "io.store"] {
+        let res = nodes.run(node1, "super", &format!("(neg get@{serv}:node2)"), "math.calc");
+        assert!(matches!(res, Err(KernErr::RemoteFault(e)) if e == "ServNotExported"));
+    }
+
+    // exported explicitly
+    nodes.run(node2, "super", "(set.exp (sys.task t))", "sys.net").unwrap();
+
+    let res = nodes.run(node1, "super", "(neg get@sys.task:node2)", "math.calc");
+    assert!(!matches!(res, Err(KernErr::RemoteFault(e)) if e == "ServNotExported"));
+}
+
+#[test]
+fn signal_checks_task_and_owner() {
+    let kerns = cluster();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    let lp = nodes.task(node1, "super", "(task.loop (neg 1)@math.calc)", "sys.task");
+    nodes.settle();
+
+    // unknown signal is not sent
+    nodes.run(node1, "super", &format!("(kil {lp})"), "sys.task").unwrap();
+
+    let res = nodes.run(node1, "node2", &format!("(int {lp})"), "sys.task");
+    assert!(matches!(res, Err(KernErr::ServAccessDenied)));
+
+    let res = nodes.run(node1, "super", "(int 1000)", "sys.task");
+    assert!(matches!(res, Err(KernErr::TaskNotFound)));
+
+    nodes.run(node1, "super", &format!("(int {lp})"), "sys.task").unwrap();
+    nodes.wait(node1, lp).unwrap();
+}
+
+#[test]
+fn placement_falls_back_without_reply() {
+    let kerns = cluster();
+    let [node1, node2] = &kerns;
+    node2.lock().set_peers(Vec::new()).unwrap();
+
+    let mut nodes = Nodes::new(&kerns);
+
+    // other node drops request, so task runs here after reply timeout
+    let res = nodes.run(node1, "super", "{task.sim:[(sum [1 2])@math.calc 5] on:node2}", "sys.task");
+    assert_eq!(res_str(res), "[3 5]");
+}
+
+#[test]
+fn deadline_of_parallel_tasks() {
+    let kerns = cluster();
+    let [node1, node2] = &kerns;
+    node2.lock().set_peers(Vec::new()).unwrap();
+
+    let mut nodes = Nodes::new(&kerns);
+
+    let res = nodes.run(node1, "super", "{task.sep:(wait 1)@time.chrono timeout:100}", "sys.task");
+    assert_eq!(res_str(res), "{err:timeout}");
+
+    let res = nodes.run(node1, "super", "{task.sim:[(wait 1)@time.chrono (neg 1)@math.calc] timeout:100}", "sys.task");
+    assert_eq!(res_str(res), "[{err:timeout} -1]");
+
+    // other node drops request, deadline passes before reply timeout
+    let res = nodes.run(node1, "super", "{task.sim:[(neg 1)@math.calc] on:node2 timeout:100}", "sys.task");
+    assert_eq!(res_str(res), "[{err:timeout}]");
+}
+
+#[test]
+fn task_from_outside_has_no_parent() {
+    let kerns = cluster();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    let a = nodes.task(node1, "super", "(wait 60)", "time.chrono");
+    nodes.settle();
+
+    // registered after other task was polled, it is not its child
+    let b = nodes.task(node1, "super", "(wait 1)", "time.chrono");
+
+    nodes.run(node1, "super", &format!("(kill {a})"), "sys.task").unwrap();
+
+    assert!(matches!(nodes.wait(node1, a), Err(KernErr::TaskKilled)));
+    nodes.wait(node1, b).unwrap();
+}
+
+#[test]
+fn queue_saved_after_retry() {
+    let kerns = apart();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    nodes.task(node1, "super", "(neg 1)@math.calc:node2", "math.calc");
+
+    for _ in 0..3 {
+        nodes.settle();
+    }
+
+    let dat = node1.lock().drv.disk.read(NET_QUEUE_NAME).unwrap().unwrap();
+    let que = NetPending::decode_queue(&dat).unwrap();
+
+    assert_eq!(que.len(), 1);
+    assert!(que[0].tries > 1);
+}
+
+#[test]
+fn relay_to_known_node_only() {
+    let kerns = cluster();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    let unknown = "0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0000:0x0009";
+
+    let res = nodes.run(node1, "super", &format!("(neg (neg 1)@math.calc:node2>{unknown})"), "math.calc");
+    assert!(matches!(res, Err(KernErr::RemoteFault(e)) if e == "NodeNotFound"));
+
+    // length of route fits in one byte
+    let route = |n: usize| {
+        let route = (0..n).map(|_| "node2").collect::<Vec<_>>().join(">");
+        Unit::parse(format!("(neg 1)@math.calc:{route}").chars()).map(|(u, _)| u)
+    };
+
+    assert!(matches!(route(255).map(|u| u.as_stream()), Ok(Some((_, _, Addr::Route(r)))) if r.len() == 255));
+    assert_eq!(route(256).err(), Some(UnitParseErr::InvalidAddr));
+}
+
+#[test]
+fn remote_stream_unreachable_node() {
+    let kerns = apart();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    let res = nodes.run(node1, "super", "(neg (neg 1)@math.calc:node2)", "math.calc");
+
+    assert!(matches!(res, Err(KernErr::DrvErr(DrvErr::Net(NetErr::Timeout)))));
+    assert!(node1.lock().net_queue.is_empty());
+}
+
+#[test]
+fn queue_entry_dropped_after_retries() {
+    let kerns = apart();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    nodes.run(node1, "super", "(set.repl (@shared t))", "io.store").unwrap();
+
+    // write for unreachable node waits in queue without author waiting for it
+    nodes.run(node1, "super", "{save:abc out:@shared.a}", "io.store").unwrap();
+
+    assert_eq!(node1.lock().net_queue.len(), 1);
+
+    // about an hour of idle ticks
+    for _ in 0..400000 {
+        nodes.sched[0].step().unwrap();
+    }
+    assert!(node1.lock().net_queue.is_empty());
+}
+
+#[test]
+fn store_disk_ops_only_super() {
+    let kerns = cluster();
+    let [node1, _] = &kerns;
+    let mut nodes = Nodes::new(&kerns);
+
+    for msg in ["flush", "(set.sync t)", "(conv bin)"] {
+        assert!(matches!(nodes.run(node1, "node2", msg, "io.store"), Err(KernErr::DbAccessDenied)));
+        nodes.run(node1, "super", msg, "io.store").unwrap();
+    }
+}
@@ -1,11 +1,23 @@
+use alloc::vec;
+use alloc::format;
 use alloc::vec::Vec;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 
 use compression::prelude::{GZipEncoder, GZipDecoder, Action, EncodeExt, DecodeExt};
 use base64ct::{Base64, Encoding};
 
-use super::core::kern::KernErr;
-use super::core::unit::{Unit, UnitAs, UnitModify, UnitNew, UnitParse, UnitParseBytesIter};
+use sha3::{Digest, Sha3_256, Shake256};
+use sha3::digest::{Update, ExtendableOutput, XofReader};
+
+use p256::{PublicKey, SecretKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+use num::BigInt;
+use num::traits::ToPrimitive;
+
+use super::core::peer::parse_remote_addr;
+use super::core::kern::{KernErr, Addr};
+use super::core::unit::{Unit, UnitAs, UnitAsBytes, UnitModify, UnitNew, UnitParse, UnitParseBytesIter};
 
 pub type Maybe<T, E> = Result<Option<T>, E>;
 
@@ -43,6 +55,11 @@ pub fn compress_bytes(b: &[u8]) -> Result<String, KernErr> {
     Ok(Base64::encode_string(&compressed))
 }
 
+pub fn compress_raw(b: &[u8]) -> Result<Vec<u8>, KernErr> {
+    let mut enc = GZipEncoder::new();
+    b.into_iter().cloned().encode(&mut enc, Action::Finish).collect::<Result<Vec<_>, _>>().map_err(|_| KernErr::CompressionFault)
+}
+
 pub fn decompress(s: &str) -> Result<String, KernErr> {
     let mut dec = GZipDecoder::new();
 
@@ -61,6 +78,62 @@ pub fn decompress_bytes(s: &str) -> Result<Vec<u8>, KernErr> {
     Ok(decompressed)
 }
 
+pub fn decompress_raw(b: &[u8]) -> Result<Vec<u8>, KernErr> {
+    let mut dec = GZipDecoder::new();
+    b.into_iter().cloned().decode(&mut dec).collect::<Result<Vec<_>, _>>().map_err(|_| KernErr::DecompressionFault)
+}
+
+fn shared_key(priv_key: &SecretKey, pub_key: &[u8], len: usize) -> Result<Vec<u8>, KernErr> {
+    let pub_key = PublicKey::from_sec1_bytes(pub_key).map_err(|_| KernErr::CreatePubKeyFault)?;
+    let shared = (pub_key.to_projective() * *priv_key.to_nonzero_scalar()).to_affine().to_encoded_point(false);
+
+    // mac key + keystream
+    let mut h = Shake256::default();
+    h.update(shared.x().ok_or(KernErr::EncryptFault)?);
+    h.update(b"vnix.enc");
+
+    let mut key = vec![0; 32 + len];
+    h.finalize_xof().read(&mut key);
+
+    Ok(key)
+}
+
+// ecies: `<ephemeral pub key><mac><data ^ keystream>`
+pub fn encrypt_raw(pub_key: &[u8], eph_key: &[u8], dat: &[u8]) -> Result<Vec<u8>, KernErr> {
+    let eph = SecretKey::from_be_bytes(eph_key).map_err(|_| KernErr::CreatePrivKeyFault)?;
+    let eph_pub = eph.public_key().to_encoded_point(true);
+
+    let key = shared_key(&eph, pub_key, dat.len())?;
+    let (mac_key, stream) = key.split_at(32);
+
+    let enc = dat.iter().zip(stream).map(|(b, k)| b ^ k).collect::<Vec<u8>>();
+    let mac = Sha3_256::new().chain_update(mac_key).chain_update(&enc).finalize();
+
+    Ok(eph_pub.as_bytes().iter()
+        .chain(mac.iter())
+        .chain(enc.iter())
+        .cloned()
+        .collect())
+}
+
+pub fn decrypt_raw(priv_key: &[u8], dat: &[u8]) -> Result<Vec<u8>, KernErr> {
+    let priv_key = SecretKey::from_be_bytes(priv_key).map_err(|_| KernErr::CreatePrivKeyFault)?;
+
+    let (eph_pub, dat) = dat.split_at_checked(33).ok_or(KernErr::DecryptFault)?;
+    let (mac, enc) = dat.split_at_checked(32).ok_or(KernErr::DecryptFault)?;
+
+    let key = shared_key(&priv_key, eph_pub, enc.len())?;
+    let (mac_key, stream) = key.split_at(32);
+
+    let _mac = Sha3_256::new().chain_update(mac_key).chain_update(enc).finalize();
+
+    if mac.iter().zip(_mac.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) != 0 {
+        return Err(KernErr::DecryptFault)
+    }
+
+    Ok(enc.iter().zip(stream).map(|(b, k)| b ^ k).collect())
+}
+
 // optimized units iterator from bytes
 pub fn unit_compressed_iterator(s: &str) -> Maybe<UnitParseBytesIter, KernErr> {
     let dat = decompress_bytes(&s)?;
@@ -79,20 +152,371 @@ pub fn hex_to_u32(s: &str) -> Option<u32> {
     None
 }
 
-#[derive(Debug)]
+pub const STORE_NAME: &'static str = "vnix.store";
+pub const STORE_ACL_NAME: &'static str = "vnix.store.acl";
+pub const STORE_REPL_NAME: &'static str = "vnix.store.repl";
+pub const STORE_MAGIC: [u8; 4] = *b"VNXS";
+pub const STORE_VERSION: u8 = 1;
+
+// how far write of other node can move logical clock forward
+const REPL_CLOCK_STEP_MAX: u64 = u32::MAX as u64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoreFmt {
+    Text,
+    Bin
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoreMode {
+    Prv,
+    Ro,
+    Pub
+}
+
+#[derive(Debug, Clone)]
+pub struct StoreAcl {
+    pub path: Vec<String>,
+    pub own: String,
+    pub mode: StoreMode,
+    pub enc: bool
+}
+
+// last write of replicated unit
+#[derive(Debug, Clone)]
+pub struct StoreVer {
+    pub path: Vec<String>,
+    pub ts: u64,
+    pub usr: String,
+    pub node: [u16; 8]
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StoreRepl {
+    pub paths: Vec<Vec<String>>,
+    pub clock: u64,
+    pub ver: Vec<StoreVer>
+}
+
+#[derive(Debug, Clone)]
 pub struct RamStore {
-    pub data: Unit
+    pub data: Unit,
+    pub acl: Vec<StoreAcl>,
+    pub repl: StoreRepl,
+    pub fmt: StoreFmt,
+    pub sync: bool,
+    pub dirty: bool
 }
 
 impl Default for RamStore {
     fn default() -> Self {
         RamStore {
-            data: Unit::map(&[])
+            data: Unit::map(&[]),
+            acl: Vec::new(),
+            repl: StoreRepl::default(),
+            fmt: StoreFmt::Text,
+            sync: false,
+            dirty: false
+        }
+    }
+}
+
+impl StoreFmt {
+    // `<magic><version><gzip(unit bytes)>` or plain vxmn text
+    pub fn encode(&self, u: Unit) -> Result<Vec<u8>, KernErr> {
+        match self {
+            StoreFmt::Text => Ok(format!("{u}").into_bytes()),
+            StoreFmt::Bin => {
+                let dat = compress_raw(&u.as_bytes())?;
+
+                Ok(STORE_MAGIC.into_iter()
+                    .chain([STORE_VERSION])
+                    .chain(dat)
+                    .collect())
+            }
+        }
+    }
+
+    pub fn decode(dat: &[u8]) -> Result<(Unit, StoreFmt), KernErr> {
+        // binary
+        if let Some(dat) = dat.strip_prefix(&STORE_MAGIC) {
+            let (ver, dat) = dat.split_first().ok_or(KernErr::DbFormatFault)?;
+
+            if *ver != STORE_VERSION {
+                return Err(KernErr::DbFormatFault)
+            }
+
+            let dat = decompress_raw(dat)?;
+            let u = Unit::parse(dat.iter()).map_err(|e| KernErr::ParseErr(e))?.0;
+
+            return Ok((u, StoreFmt::Bin))
         }
+
+        // text
+        let s = core::str::from_utf8(dat).map_err(|_| KernErr::DecodeFault)?;
+        let u = Unit::parse(s.chars()).map_err(|e| KernErr::ParseErr(e))?.0;
+
+        Ok((u, StoreFmt::Text))
+    }
+}
+
+impl StoreMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "prv" => Some(StoreMode::Prv),
+            "ro" => Some(StoreMode::Ro),
+            "pub" => Some(StoreMode::Pub),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StoreMode::Prv => "prv",
+            StoreMode::Ro => "ro",
+            StoreMode::Pub => "pub"
+        }
+    }
+}
+
+impl StoreAcl {
+    pub fn as_unit(&self) -> Unit {
+        Unit::map(&[
+            (Unit::str("path"), Unit::path(&self.path.iter().map(|s| s.as_str()).collect::<Vec<_>>())),
+            (Unit::str("own"), Unit::str(&self.own)),
+            (Unit::str("mode"), Unit::str(self.mode.as_str())),
+            (Unit::str("enc"), Unit::bool(self.enc))
+        ])
+    }
+
+    pub fn from_unit(u: Unit) -> Option<Self> {
+        Some(StoreAcl {
+            path: u.clone().as_map_find("path")?.as_path()?.to_vec(),
+            own: u.clone().as_map_find("own")?.as_str()?.to_string(),
+            mode: StoreMode::parse(&u.clone().as_map_find("mode")?.as_str()?)?,
+            enc: u.as_map_find("enc").and_then(|u| u.as_bool()).unwrap_or(false)
+        })
+    }
+}
+
+impl StoreRepl {
+    pub fn as_unit(&self) -> Unit {
+        let path = |path: &Vec<String>| Unit::path(&path.iter().map(|s| s.as_str()).collect::<Vec<_>>());
+
+        let ver = self.ver.iter().map(|v| Unit::map(&[
+            (Unit::str("path"), path(&v.path)),
+            (Unit::str("ts"), Unit::int_big(BigInt::from(v.ts))),
+            (Unit::str("usr"), Unit::str(&v.usr)),
+            (Unit::str("node"), Unit::str(&Addr::Remote(v.node).to_string()))
+        ])).collect::<Vec<_>>();
+
+        Unit::map(&[
+            (Unit::str("paths"), Unit::list(&self.paths.iter().map(path).collect::<Vec<_>>())),
+            (Unit::str("clock"), Unit::int_big(BigInt::from(self.clock))),
+            (Unit::str("ver"), Unit::list(&ver))
+        ])
+    }
+
+    pub fn from_unit(u: Unit) -> Option<Self> {
+        let paths = u.clone().as_map_find("paths")?.as_list()?.iter().map(|u| u.clone().as_path().map(|p| p.to_vec())).collect::<Option<Vec<_>>>()?;
+
+        let ver = u.clone().as_map_find("ver")?.as_list()?.iter().map(|u| Some(StoreVer {
+            path: u.clone().as_map_find("path")?.as_path()?.to_vec(),
+            ts: u.clone().as_map_find("ts")?.as_int_big()?.to_u64()?,
+            usr: u.clone().as_map_find("usr")?.as_str()?.to_string(),
+            node: parse_remote_addr(&u.clone().as_map_find("node")?.as_str()?)?
+        })).collect::<Option<Vec<_>>>()?;
+
+        Some(StoreRepl {
+            paths,
+            clock: u.as_map_find("clock")?.as_int_big()?.to_u64()?,
+            ver
+        })
+    }
+
+    pub fn covers(&self, path: &[String]) -> bool {
+        self.paths.iter().any(|p| path.starts_with(p))
+    }
+
+    // newer writes inside path are kept
+    fn record(&mut self, ver: StoreVer) {
+        self.ver.retain(|v| !v.path.starts_with(&ver.path) || v.is_after(&ver));
+        self.ver.push(ver);
+    }
+
+    // lamport timestamp of local write
+    pub fn write(&mut self, path: &[String], usr: &str, node: [u16; 8]) -> Result<u64, KernErr> {
+        self.clock = self.clock.checked_add(1).ok_or(KernErr::DbSaveFault)?;
+        self.record(StoreVer {path: path.to_vec(), ts: self.clock, usr: usr.into(), node});
+
+        Ok(self.clock)
+    }
+
+    // returns paths inside unit with later writes, they must be kept
+    pub fn apply(&mut self, ver: StoreVer) -> Result<Option<Vec<Vec<String>>>, KernErr> {
+        // clock cannot be moved close to overflow by one write
+        if ver.ts > self.clock.saturating_add(REPL_CLOCK_STEP_MAX) {
+            return Err(KernErr::DbSaveFault)
+        }
+
+        self.clock = self.clock.max(ver.ts);
+
+        if self.ver.iter().any(|v| ver.path.starts_with(&v.path) && !ver.is_after(v)) {
+            return Ok(None)
+        }
+
+        let later = self.ver.iter()
+            .filter(|v| v.path.len() > ver.path.len() && v.path.starts_with(&ver.path) && v.is_after(&ver))
+            .map(|v| v.path.clone())
+            .collect();
+
+        self.record(ver);
+        Ok(Some(later))
+    }
+}
+
+impl StoreVer {
+    // last writer wins, ties are broken by user name and then by node
+    pub fn is_after(&self, other: &StoreVer) -> bool {
+        (self.ts, self.usr.as_str(), self.node) > (other.ts, other.usr.as_str(), other.node)
     }
 }
 
 impl RamStore {
+    pub const SUPER: &'static str = "super";
+    pub const SYS: &'static str = "sys";
+
+    // most specific rule covering path
+    pub fn rule(&self, path: &[String]) -> Option<&StoreAcl> {
+        self.acl.iter()
+            .filter(|acl| path.starts_with(&acl.path))
+            .max_by_key(|acl| acl.path.len())
+    }
+
+    // encrypted unit containing path
+    pub fn enc_rule(&self, path: &[String]) -> Option<&StoreAcl> {
+        self.acl.iter().find(|acl| acl.enc && path.starts_with(&acl.path))
+    }
+
+    // entries without rule belong to `super` as read-only if they are already exist
+    fn owned(&self, path: &[String]) -> bool {
+        (1..=path.len()).any(|i| self.data.find(path[0..i].iter().map(|s| s.as_str())).is_some())
+    }
+
+    pub fn can_read(&self, ath: &str, path: &[String]) -> bool {
+        if ath == Self::SUPER {
+            return true
+        }
+
+        match self.rule(path) {
+            Some(acl) => acl.mode != StoreMode::Prv || acl.own == ath,
+            None => true
+        }
+    }
+
+    pub fn can_write(&self, ath: &str, path: &[String]) -> bool {
+        if ath == Self::SUPER {
+            return true
+        }
+
+        // kernel config, root covers it too
+        if path.first().map_or(true, |s| s == Self::SYS) {
+            return false
+        }
+
+        let allowed = match self.rule(path) {
+            Some(acl) => acl.mode == StoreMode::Pub || acl.own == ath,
+            None => !self.owned(path)
+        };
+
+        // nested entries of other users
+        allowed && self.acl.iter()
+            .filter(|acl| acl.path.len() > path.len() && acl.path.starts_with(path))
+            .all(|acl| acl.mode == StoreMode::Pub || acl.own == ath)
+    }
+
+    // path and all nested entries belong to user
+    pub fn owned_by(&self, ath: &str, path: &[String]) -> bool {
+        self.rule(path).map_or(true, |acl| acl.own == ath) && self.acl.iter()
+            .filter(|acl| acl.path.len() > path.len() && acl.path.starts_with(path))
+            .all(|acl| acl.own == ath)
+    }
+
+    pub fn can_admin(&self, ath: &str, path: &[String]) -> bool {
+        if ath == Self::SUPER {
+            return true
+        }
+
+        match self.rule(path) {
+            Some(acl) => acl.own == ath,
+            None => false
+        }
+    }
+
+    // unit with hidden private entries of other users
+    pub fn view(&self, ath: &str, key: Unit) -> Option<Unit> {
+        let path = key.clone().as_path()?;
+
+        if !self.can_read(ath, &path) {
+            return None
+        }
+
+        let mut u = self.load(key)?;
+
+        for acl in self.acl.iter().filter(|acl| acl.path.len() > path.len() && acl.path.starts_with(&path)) {
+            if !self.can_read(ath, &acl.path) {
+                if let Some(_u) = u.clone().remove(acl.path[path.len()..].iter().map(|s| s.as_str())) {
+                    u = _u;
+                }
+            }
+        }
+        Some(u)
+    }
+
+    pub fn grant(&mut self, ath: &str, path: &[String]) {
+        if ath == Self::SUPER || self.rule(path).is_some() {
+            return
+        }
+
+        self.acl.push(StoreAcl {
+            path: path.to_vec(),
+            own: ath.into(),
+            mode: StoreMode::Ro,
+            enc: false
+        });
+        self.dirty = true;
+    }
+
+    pub fn chmod(&mut self, path: &[String], mode: StoreMode) {
+        if let Some(acl) = self.acl.iter_mut().find(|acl| acl.path == path) {
+            acl.mode = mode;
+        } else {
+            let own = self.rule(path).map(|acl| acl.own.clone()).unwrap_or(Self::SUPER.into());
+            self.acl.push(StoreAcl {path: path.to_vec(), own, mode, enc: false});
+        }
+        self.dirty = true;
+    }
+
+    pub fn chown(&mut self, path: &[String], own: &str) {
+        if let Some(acl) = self.acl.iter_mut().find(|acl| acl.path == path) {
+            acl.own = own.into();
+        } else {
+            let mode = self.rule(path).map(|acl| acl.mode).unwrap_or(StoreMode::Ro);
+            self.acl.push(StoreAcl {path: path.to_vec(), own: own.into(), mode, enc: false});
+        }
+        self.dirty = true;
+    }
+
+    pub fn set_enc(&mut self, path: &[String]) {
+        if let Some(acl) = self.acl.iter_mut().find(|acl| acl.path == path) {
+            acl.enc = true;
+        } else {
+            let (own, mode) = self.rule(path).map(|acl| (acl.own.clone(), acl.mode)).unwrap_or((Self::SUPER.into(), StoreMode::Ro));
+            self.acl.push(StoreAcl {path: path.to_vec(), own, mode, enc: true});
+        }
+        self.dirty = true;
+    }
+
     pub fn load(&self, key: Unit) -> Option<Unit> {
         if let Some(path) = key.as_path() {
             return self.data.find(path.iter().map(|s| s.as_str()));
@@ -100,11 +524,235 @@ impl RamStore {
         None
     }
 
-    pub fn save(&mut self, key: Unit, val: Unit) {
-        if let Some(path) = key.as_path() {
-            if let Some(data) = self.data.clone().merge(path.iter().map(|s| s.as_str()), val) {
-                self.data = data;
-            }
+    pub fn save(&mut self, key: Unit, val: Unit) -> Option<()> {
+        let path = key.as_path()?;
+
+        self.data = self.data.clone().merge(path.iter().map(|s| s.as_str()), val)?;
+        self.dirty = true;
+
+        Some(())
+    }
+
+    pub fn has(&self, ath: &str, key: Unit) -> bool {
+        self.view(ath, key).is_some()
+    }
+
+    pub fn keys(&self, ath: &str, key: Unit) -> Option<Vec<Unit>> {
+        Self::keys_of(self.view(ath, key)?)
+    }
+
+    pub fn keys_of(u: Unit) -> Option<Vec<Unit>> {
+        if let Some(map) = u.clone().as_map() {
+            return Some(map.iter().map(|(k, _)| k.clone()).collect())
+        }
+
+        if let Some(lst) = u.as_list() {
+            return Some((0..lst.len()).map(|i| Unit::uint(i as u32)).collect())
+        }
+        None
+    }
+
+    pub fn remove(&mut self, key: Unit) -> Option<()> {
+        let path = key.as_path()?;
+
+        self.data = self.data.clone().remove(path.iter().map(|s| s.as_str()))?;
+        self.acl.retain(|acl| !acl.path.starts_with(&path));
+        self.dirty = true;
+
+        Some(())
+    }
+
+    pub fn mv(&mut self, from: Unit, to: Unit) -> Option<()> {
+        let from_path = from.clone().as_path()?;
+        let to_path = to.clone().as_path()?;
+
+        // cannot move subtree inside itself
+        if to_path.starts_with(from_path.as_slice()) {
+            return None
+        }
+
+        let u = self.load(from.clone())?;
+        let mut data = self.data.clone().remove(from_path.iter().map(|s| s.as_str()))?;
+
+        if let Some(_data) = data.clone().remove(to_path.iter().map(|s| s.as_str())) {
+            data = _data;
+        }
+
+        self.data = data.merge(to_path.iter().map(|s| s.as_str()), u)?;
+
+        let (moved, rest): (Vec<_>, Vec<_>) = self.acl.drain(..).partition(|acl| acl.path.starts_with(&from_path));
+
+        self.acl = rest.into_iter()
+            .filter(|acl| !acl.path.starts_with(&to_path))
+            .chain(moved.into_iter().map(|mut acl| {
+                acl.path = to_path.iter().chain(acl.path[from_path.len()..].iter()).cloned().collect();
+                acl
+            }))
+            .collect();
+
+        self.dirty = true;
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use alloc::string::String;
+
+    use p256::SecretKey;
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+    use super::{RamStore, StoreFmt, StoreMode, STORE_MAGIC, STORE_VERSION, encrypt_raw, decrypt_raw};
+    use super::super::core::kern::KernErr;
+    use super::super::core::unit::{Unit, UnitAs, UnitNew};
+
+    const SECRET: &[u8] = b"hello, vnix";
+
+    fn path(s: &str) -> Vec<String> {
+        s.split('.').map(String::from).collect()
+    }
+
+    fn key(s: &str) -> Unit {
+        Unit::path(&s.split('.').collect::<Vec<_>>())
+    }
+
+    fn store_unit() -> Unit {
+        Unit::map(&[
+            (Unit::str("txt"), Unit::map(&[(Unit::str("hello"), Unit::str("Hello, vnix!"))])),
+            (Unit::str("lst"), Unit::list(&[Unit::uint(1), Unit::int(-2), Unit::int(-300), Unit::int(-70000), Unit::bool(true)]))
+        ])
+    }
+
+    #[test]
+    fn store_fmt_round_trip() {
+        for fmt in [StoreFmt::Text, StoreFmt::Bin] {
+            let dat = fmt.encode(store_unit()).unwrap();
+            assert_eq!(dat.starts_with(&STORE_MAGIC), fmt == StoreFmt::Bin);
+
+            let (u, _fmt) = StoreFmt::decode(&dat).unwrap();
+            assert_eq!((u, _fmt), (store_unit(), fmt));
+        }
+    }
+
+    #[test]
+    fn store_fmt_bad_magic() {
+        let mut dat = StoreFmt::Bin.encode(store_unit()).unwrap();
+        dat[0] ^= 0x01;
+
+        assert!(StoreFmt::decode(&dat).is_err());
+    }
+
+    #[test]
+    fn store_fmt_bad_version() {
+        let mut dat = StoreFmt::Bin.encode(store_unit()).unwrap();
+        dat[STORE_MAGIC.len()] = STORE_VERSION + 1;
+
+        assert!(matches!(StoreFmt::decode(&dat), Err(KernErr::DbFormatFault)));
+        assert!(matches!(StoreFmt::decode(&STORE_MAGIC), Err(KernErr::DbFormatFault)));
+    }
+
+    fn keys(seed: u8) -> ([u8; 32], Vec<u8>) {
+        let key = SecretKey::from_be_bytes(&[seed; 32]).unwrap();
+        ([seed; 32], key.public_key().to_encoded_point(true).as_bytes().to_vec())
+    }
+
+    #[test]
+    fn ecies_round_trip() {
+        let (priv_key, pub_key) = keys(1);
+        let enc = encrypt_raw(&pub_key, &[2; 32], SECRET).unwrap();
+
+        // ephemeral key, mac and data of same length
+        assert_eq!(enc.len(), 33 + 32 + SECRET.len());
+        assert!(!enc.windows(SECRET.len()).any(|w| w == SECRET));
+
+        assert_eq!(decrypt_raw(&priv_key, &enc).unwrap(), SECRET);
+    }
+
+    #[test]
+    fn ecies_wrong_key_rejected() {
+        let (_, pub_key) = keys(1);
+        let enc = encrypt_raw(&pub_key, &[2; 32], SECRET).unwrap();
+
+        assert!(matches!(decrypt_raw(&keys(3).0, &enc), Err(KernErr::DecryptFault)));
+    }
+
+    #[test]
+    fn ecies_tampered_rejected() {
+        let (priv_key, pub_key) = keys(1);
+        let enc = encrypt_raw(&pub_key, &[2; 32], SECRET).unwrap();
+
+        // mac and data
+        for i in 33..enc.len() {
+            let mut bad = enc.clone();
+            bad[i] ^= 0x01;
+
+            assert!(matches!(decrypt_raw(&priv_key, &bad), Err(KernErr::DecryptFault)), "byte {i} is not covered");
         }
+
+        assert!(decrypt_raw(&priv_key, &enc[..64]).is_err());
+    }
+
+    #[test]
+    fn owner_only_entry() {
+        let mut store = RamStore::default();
+
+        store.save(key("txt.a"), Unit::uint(1)).unwrap();
+        store.grant("alice", &path("txt.a"));
+        store.chmod(&path("txt.a"), StoreMode::Prv);
+
+        assert!(store.can_read("alice", &path("txt.a")));
+        assert!(store.can_read(RamStore::SUPER, &path("txt.a")));
+        assert!(!store.can_read("bob", &path("txt.a")));
+        assert!(!store.can_read("bob", &path("txt.a.b")));
+
+        // hidden from view of parent too
+        assert!(!store.has("bob", key("txt.a")));
+        assert!(store.view("bob", key("txt")).unwrap().as_map_find("a").is_none());
+        assert!(store.view("alice", key("txt")).unwrap().as_map_find("a").is_some());
+    }
+
+    #[test]
+    fn write_under_foreign_entry() {
+        let mut store = RamStore::default();
+
+        store.save(key("txt.a"), Unit::uint(1)).unwrap();
+        store.grant("alice", &path("txt.a"));
+
+        assert!(store.can_write("alice", &path("txt.a.b")));
+        assert!(!store.can_write("bob", &path("txt.a.b")));
+
+        // parent without data of its own still covers nested rule
+        store.grant("alice", &path("doc.a"));
+
+        assert!(store.can_write("bob", &path("note")));
+        assert!(!store.can_write("bob", &path("doc")));
+
+        store.chmod(&path("txt.a"), StoreMode::Pub);
+        assert!(store.can_write("bob", &path("txt.a.b")));
+    }
+
+    #[test]
+    fn acl_follows_mv() {
+        let mut store = RamStore::default();
+
+        store.save(key("txt.a"), Unit::uint(1)).unwrap();
+        store.grant("alice", &path("txt.a"));
+        store.chmod(&path("txt.a"), StoreMode::Prv);
+
+        store.save(key("doc.b"), Unit::uint(2)).unwrap();
+        store.grant("bob", &path("doc.b"));
+
+        // rule of replaced entry is dropped
+        store.mv(key("txt.a"), key("doc.b")).unwrap();
+
+        assert!(store.rule(&path("txt.a")).is_none());
+
+        let acl = store.rule(&path("doc.b")).unwrap();
+        assert_eq!((acl.own.as_str(), acl.mode), ("alice", StoreMode::Prv));
+
+        assert!(!store.can_read("bob", &path("doc.b")));
+        assert_eq!(store.load(key("doc.b")), Some(Unit::uint(1)));
     }
 }
@@ -3,8 +3,16 @@ use core::task::Poll;
 use core::task::Context;
 use core::future::Future;
 
+use std::fs;
+use std::fs::File;
 use std::io::stdout;
+use std::io::Read;
 use std::io::Write;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::net::{Ipv6Addr, SocketAddr, TcpListener};
+
+use mio::net::TcpStream;
 
 use std::time::Instant;
 use rand::SeedableRng;
@@ -19,7 +27,9 @@ use linuxfb::Framebuffer;
 use crossterm::{cursor, event, style, terminal, ExecutableCommand, QueueableCommand};
 
 use crate::vnix::utils::Maybe;
-use crate::vnix::core::driver::{CLI, CLIErr, DispErr, DrvErr, Disp, TermKey, Time, TimeErr, Rnd, RndErr, Mem, MemErr, MemSizeUnits, Mouse, Duration, TimeUnit};
+use crate::vnix::core::user::Usr;
+use crate::vnix::core::peer::NODE_USR;
+use crate::vnix::core::driver::{CLI, CLIErr, DispErr, DrvErr, Disp, TermKey, Time, TimeErr, Rnd, RndErr, Mem, MemErr, MemSizeUnits, Mouse, Duration, TimeUnit, Disk, DiskErr, Net, NetErr};
 
 
 pub struct LinuxCLI {
@@ -38,6 +48,40 @@ pub struct LinuxRnd;
 
 pub struct LinuxMem;
 
+pub struct LinuxDisk {
+    root: PathBuf
+}
+
+pub struct LinuxNet {
+    addr: Ipv6Addr,
+    port: u16,
+    listener: Option<TcpListener>,
+    node: Option<Usr>,
+    peers: Vec<([u16; 8], String)>,
+    conns: Vec<LinuxNetConn>
+}
+
+#[derive(Debug, PartialEq)]
+enum LinuxNetConnState {
+    Connect, // outgoing, waits for challenge
+    Hello([u8; 16]), // incoming, waits for hello signed over challenge and challenge of other side
+    Verify([u8; 16]), // outgoing, waits for hello signed over own challenge
+    Ready,
+    Closed
+}
+
+// both sides prove node key, then send frames
+struct LinuxNetConn {
+    addr: [u16; 8], // known after handshake for incoming connection
+    incoming: bool,
+    verified: bool,
+    stream: TcpStream,
+    state: LinuxNetConnState,
+    since: Instant,
+    buf: Vec<u8>,
+    out: Vec<u8>
+}
+
 
 struct LinuxSleepAsync {
     done: bool,
@@ -80,6 +124,256 @@ impl LinuxTime {
     }
 }
 
+impl LinuxDisk {
+    pub fn new(root: &str) -> Result<Self, DrvErr> {
+        let root = PathBuf::from(root);
+
+        if !root.is_dir() {
+            return Err(DrvErr::DriverFault)
+        }
+
+        // temp files left by write interrupted by crash
+        let stale = fs::read_dir(&root).map_err(|_| DrvErr::DriverFault)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "tmp"))
+            .collect::<Vec<_>>();
+
+        for path in stale {
+            fs::remove_file(path).map_err(|_| DrvErr::DriverFault)?;
+        }
+        Ok(LinuxDisk {root})
+    }
+
+    // plain file inside root, temp names are reserved for writes
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        if name.is_empty() || name.contains('/') || name.contains("..") || name.ends_with(".tmp") {
+            return None
+        }
+        Some(self.root.join(name))
+    }
+}
+
+impl LinuxNet {
+    pub const PORT: u16 = 7337;
+    const MAX_FRAME: usize = 64 * 1024 * 1024;
+    const MAX_HELLO: usize = 1024;
+    const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+    pub fn new(addr: &str, port: u16) -> Result<Self, DrvErr> {
+        let addr = addr.parse::<Ipv6Addr>().map_err(|_| DrvErr::DriverFault)?;
+
+        Ok(LinuxNet {
+            addr,
+            port,
+            listener: None,
+            node: None,
+            peers: Vec::new(),
+            conns: Vec::new()
+        })
+    }
+
+    // `<node addr><sign len: u16 le><sign>`, signature covers challenge and addresses of both sides;
+    // dialing side sends its challenge after hello, accepting side answers with own hello
+    fn hello(&self, challenge: &[u8], to: [u16; 8]) -> Option<Vec<u8>> {
+        let addr = self.addr.octets();
+        let signed = [challenge, &addr, &Ipv6Addr::from(to).octets()].concat();
+        let sign = self.node.as_ref()?.sign_bytes(&signed).ok()?;
+
+        Some(addr.into_iter().chain((sign.len() as u16).to_le_bytes()).chain(sign.into_bytes()).collect())
+    }
+
+    fn challenge() -> [u8; 16] {
+        let mut challenge = [0; 16];
+        StdRng::from_entropy().fill_bytes(&mut challenge);
+
+        challenge
+    }
+
+    // `<len: u16 le>` of hello, if it is received with `extra` bytes after it
+    fn hello_len(buf: &[u8], extra: usize) -> Option<usize> {
+        let len = 18 + u16::from_le_bytes(buf.get(16..18)?.try_into().ok()?) as usize;
+        Some(len).filter(|len| buf.len() >= len + extra)
+    }
+
+    // address of known node, that signed hello
+    fn check_hello(&self, challenge: &[u8; 16], dat: &[u8]) -> Option<[u16; 8]> {
+        let addr: [u8; 16] = dat.get(0..16)?.try_into().ok()?;
+        let len = u16::from_le_bytes(dat.get(16..18)?.try_into().ok()?) as usize;
+        let sign = core::str::from_utf8(dat.get(18..18 + len)?).ok()?;
+
+        let addr = Ipv6Addr::from(addr).segments();
+        let (_, key) = self.peers.iter().find(|(a, _)| *a == addr)?;
+
+        let signed = [challenge.as_slice(), &Ipv6Addr::from(addr).octets(), &self.addr.octets()].concat();
+        Usr::guest(NODE_USR, key, key).ok()?.verify_bytes(&signed, sign).ok()?;
+
+        Some(addr)
+    }
+
+    fn accept(&mut self) -> Result<(), NetErr> {
+        let listener = if let Some(listener) = &self.listener {
+            listener
+        } else {
+            return Ok(())
+        };
+
+        loop {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(_) => return Err(NetErr::Listen)
+            };
+
+            if stream.set_nonblocking(true).is_err() {
+                continue;
+            }
+
+            // other side proves its node key by signing random challenge
+            let challenge = Self::challenge();
+            let mut conn = LinuxNetConn::new([0; 8], true, TcpStream::from_std(stream), LinuxNetConnState::Hello(challenge));
+            conn.out.extend_from_slice(&challenge);
+
+            self.conns.push(conn);
+        }
+    }
+
+    // move handshakes forward, read and write what sockets allow
+    fn poll(&mut self) -> Result<(), NetErr> {
+        self.accept()?;
+
+        for i in 0..self.conns.len() {
+            self.conns[i].read();
+
+            if self.conns[i].state != LinuxNetConnState::Ready && self.conns[i].since.elapsed() > Self::HANDSHAKE_TIMEOUT {
+                self.conns[i].state = LinuxNetConnState::Closed;
+            }
+
+            match self.conns[i].state {
+                // sign challenge of accepting side and send own one
+                LinuxNetConnState::Connect => {
+                    if let Some(challenge) = self.conns[i].buf.get(0..16).map(|b| b.to_vec()) {
+                        match self.hello(&challenge, self.conns[i].addr) {
+                            Some(hello) => {
+                                let own = Self::challenge();
+
+                                let conn = &mut self.conns[i];
+                                conn.buf.drain(0..16);
+                                conn.out = [hello.as_slice(), &own].concat();
+                                conn.state = LinuxNetConnState::Verify(own);
+                            },
+                            None => self.conns[i].state = LinuxNetConnState::Closed
+                        }
+                    }
+                },
+                LinuxNetConnState::Hello(challenge) => {
+                    if let Some(len) = Self::hello_len(&self.conns[i].buf, 16) {
+                        let other = self.conns[i].buf[len..len + 16].to_vec();
+                        let hello = self.check_hello(&challenge, &self.conns[i].buf).and_then(|addr| Some((addr, self.hello(&other, addr)?)));
+
+                        match hello {
+                            Some((addr, hello)) => {
+                                // authenticated connection replaces previous incoming one of the same node
+                                self.conns.iter_mut().filter(|c| c.incoming && c.addr == addr).for_each(|c| c.state = LinuxNetConnState::Closed);
+
+                                let conn = &mut self.conns[i];
+                                conn.buf.drain(0..len + 16);
+                                conn.out.extend(hello);
+                                conn.addr = addr;
+                                conn.verified = true;
+                                conn.state = LinuxNetConnState::Ready;
+                            },
+                            None => self.conns[i].state = LinuxNetConnState::Closed
+                        }
+                    } else if self.conns[i].buf.len() > Self::MAX_HELLO {
+                        self.conns[i].state = LinuxNetConnState::Closed;
+                    }
+                },
+                // dialed address must be the node, that signed own challenge
+                LinuxNetConnState::Verify(challenge) => {
+                    if let Some(len) = Self::hello_len(&self.conns[i].buf, 0) {
+                        if self.check_hello(&challenge, &self.conns[i].buf) == Some(self.conns[i].addr) {
+                            let conn = &mut self.conns[i];
+                            conn.buf.drain(0..len);
+                            conn.verified = true;
+                            conn.state = LinuxNetConnState::Ready;
+                        } else {
+                            self.conns[i].state = LinuxNetConnState::Closed;
+                        }
+                    } else if self.conns[i].buf.len() > Self::MAX_HELLO {
+                        self.conns[i].state = LinuxNetConnState::Closed;
+                    }
+                },
+                _ => ()
+            }
+
+            self.conns[i].flush();
+        }
+
+        // keep closed connections with unread frames
+        self.conns.retain(|c| c.state != LinuxNetConnState::Closed || !c.buf.is_empty());
+        Ok(())
+    }
+}
+
+impl LinuxNetConn {
+    fn new(addr: [u16; 8], incoming: bool, stream: TcpStream, state: LinuxNetConnState) -> Self {
+        LinuxNetConn {addr, incoming, verified: false, stream, state, since: Instant::now(), buf: Vec::new(), out: Vec::new()}
+    }
+
+    fn read(&mut self) {
+        let mut tmp = [0; 4096];
+
+        while self.state != LinuxNetConnState::Closed {
+            match self.stream.read(&mut tmp) {
+                Ok(0) => self.state = LinuxNetConnState::Closed,
+                Ok(n) => self.buf.extend_from_slice(&tmp[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::NotConnected => break,
+                Err(_) => self.state = LinuxNetConnState::Closed
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        while !self.out.is_empty() && self.state != LinuxNetConnState::Closed {
+            match self.stream.write(&self.out) {
+                Ok(0) => self.state = LinuxNetConnState::Closed,
+                Ok(n) => {
+                    self.out.drain(0..n);
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::NotConnected => break,
+                Err(_) => self.state = LinuxNetConnState::Closed
+            }
+        }
+    }
+
+    // `<len: u32 le><data>`, closed connection gives frames received before it is closed
+    fn frame(&mut self) -> Option<Vec<u8>> {
+        match self.state {
+            LinuxNetConnState::Ready => (),
+            LinuxNetConnState::Closed if self.verified => (),
+            _ => return None
+        }
+
+        let len = self.buf.get(0..4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+        let complete = len.map_or(false, |len| len <= LinuxNet::MAX_FRAME && self.buf.len() >= 4 + len);
+
+        if !complete {
+            if len.map_or(false, |len| len > LinuxNet::MAX_FRAME) || self.state == LinuxNetConnState::Closed {
+                self.state = LinuxNetConnState::Closed;
+                self.buf.clear();
+            }
+            return None
+        }
+
+        let len = len?;
+        let dat = self.buf.get(4..4 + len)?.to_vec();
+        self.buf.drain(..4 + len);
+
+        Some(dat)
+    }
+}
+
 impl LinuxSleepAsync {
     fn new(dur: core::time::Duration) -> Self {
         Self {
@@ -357,3 +651,115 @@ impl Mem for LinuxMem {
         }
     }
 }
+
+impl Disk for LinuxDisk {
+    fn read(&self, name: &str) -> Maybe<Vec<u8>, DiskErr> {
+        let path = self.path(name).ok_or(DiskErr::Read)?;
+
+        match fs::read(path) {
+            Ok(dat) => Ok(Some(dat)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(_) => Err(DiskErr::Read)
+        }
+    }
+
+    fn write(&mut self, name: &str, dat: &[u8]) -> Result<(), DiskErr> {
+        let path = self.path(name).ok_or(DiskErr::Write)?;
+        let tmp_path = self.root.join(format!("{name}.tmp"));
+
+        // write to temp file first, so crash never leaves half-written blob
+        let mut tmp = File::create(&tmp_path).map_err(|_| DiskErr::Write)?;
+
+        tmp.write_all(dat).map_err(|_| DiskErr::Write)?;
+        tmp.sync_all().map_err(|_| DiskErr::Write)?;
+
+        // atomic replace
+        fs::rename(&tmp_path, &path).map_err(|_| DiskErr::Write)?;
+        File::open(&self.root).and_then(|dir| dir.sync_all()).map_err(|_| DiskErr::Write)?;
+
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<String>, DiskErr> {
+        let names = fs::read_dir(&self.root).map_err(|_| DiskErr::List)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|name| !name.ends_with(".tmp"))
+            .collect();
+
+        Ok(names)
+    }
+}
+
+impl Net for LinuxNet {
+    fn addr(&self) -> [u16; 8] {
+        self.addr.segments()
+    }
+
+    fn auth(&mut self, node: &Usr, peers: &[([u16; 8], String)]) {
+        self.node = Some(node.clone());
+        self.peers = peers.to_vec();
+
+        // connections of removed nodes
+        for conn in self.conns.iter_mut().filter(|c| c.addr != [0; 8] && !peers.iter().any(|(a, _)| *a == c.addr)) {
+            conn.state = LinuxNetConnState::Closed;
+        }
+    }
+
+    fn listen(&mut self) -> Result<(), NetErr> {
+        let listener = TcpListener::bind(SocketAddr::from((self.addr, self.port))).map_err(|_| NetErr::Listen)?;
+        listener.set_nonblocking(true).map_err(|_| NetErr::Listen)?;
+
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    // start connection without waiting, frames wait until handshake is done
+    fn connect(&mut self, addr: [u16; 8]) -> Result<(), NetErr> {
+        self.poll()?;
+
+        if let Some(conn) = self.conns.iter().find(|c| c.addr == addr && c.state != LinuxNetConnState::Closed) {
+            return match conn.state {
+                LinuxNetConnState::Ready => Ok(()),
+                _ => Err(NetErr::Pending)
+            }
+        }
+
+        let sock = SocketAddr::from((Ipv6Addr::from(addr), self.port));
+        let stream = TcpStream::connect(sock).map_err(|_| NetErr::Unreachable)?;
+
+        stream.set_nodelay(true).map_err(|_| NetErr::Connect)?;
+        self.conns.push(LinuxNetConn::new(addr, false, stream, LinuxNetConnState::Connect));
+
+        Err(NetErr::Pending)
+    }
+
+    fn send(&mut self, addr: [u16; 8], dat: &[u8]) -> Result<(), NetErr> {
+        self.connect(addr)?;
+
+        let conn = self.conns.iter_mut().find(|c| c.addr == addr && c.state == LinuxNetConnState::Ready).ok_or(NetErr::Unreachable)?;
+
+        // slow node
+        if conn.out.len() + dat.len() > LinuxNet::MAX_FRAME {
+            return Err(NetErr::Send)
+        }
+
+        conn.out.extend((dat.len() as u32).to_le_bytes());
+        conn.out.extend_from_slice(dat);
+        conn.flush();
+
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Maybe<([u16; 8], Vec<u8>), NetErr> {
+        self.poll()?;
+
+        for conn in self.conns.iter_mut() {
+            if let Some(dat) = conn.frame() {
+                return Ok(Some((conn.addr, dat)))
+            }
+        }
+        Ok(None)
+    }
+}
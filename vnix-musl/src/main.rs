@@ -18,13 +18,12 @@ use ::vnix::content;
 use spin::Mutex;
 
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::boxed::Box;
-use alloc::string::String;
 
 use core::fmt::Write;
 
-use std::io::Read;
-use std::{thread, fs::File, time::Duration};
+use std::{env, thread, time::Duration};
 
 use vnix::vnix_entry;
 use vnix::core::kern::Kern;
@@ -34,19 +33,10 @@ use vnix::serv::io::term::base;
 
 use crate::vnix::core::driver::CLI;
 use crate::vnix::core::driver::Disp;
+use crate::vnix::core::driver::Net;
 use crate::vnix::core::driver::MemSizeUnits;
-use crate::vnix::core::unit::{Unit, UnitParse};
 
 
-fn load_store() -> Option<Unit> {
-    let mut store_file = File::open("vnix.store").ok()?;
-
-    let mut store_s = String::new();
-    store_file.read_to_string(&mut store_s).ok()?;
-
-    Some(Unit::parse(store_s.chars()).ok()?.0)
-}
-
 fn main() {
     // load drivers
 
@@ -73,6 +63,24 @@ fn main() {
     let rnd = driver::linux::LinuxRnd;
     let mem = driver::linux::LinuxMem;
 
+    // disk
+    let disk = driver::linux::LinuxDisk::new(".");
+
+    if disk.is_err() {
+        println!("ERR loader:disk: not available");
+        return;
+    }
+
+    // net
+    let net_addr = env::args().skip_while(|arg| arg != "--net").nth(1).unwrap_or("::1".into());
+    let net = driver::linux::LinuxNet::new(&net_addr, driver::linux::LinuxNet::PORT);
+
+    if net.is_err() {
+        println!("WARN loader:net: not available, using loopback driver");
+    }
+
+    let net_stub = ::vnix::driver::stub::LoopNet::new([0, 0, 0, 0, 0, 0, 0, 1], Arc::new(Mutex::new(Default::default())));
+
     // kernel console
     let term = Rc::new(Mutex::new(base::Term::new(&content::SYS_FONT)));
 
@@ -88,21 +96,16 @@ fn main() {
         Box::new(time),
         // rnd.map(|p| Box::new(p) as Box<dyn Rnd>).unwrap_or(Box::new(prng) as Box<dyn Rnd>),
         Box::new(rnd),
-        Box::new(mem)
+        Box::new(mem),
+        Box::new(disk.unwrap()),
+        net.map(|p| Box::new(p) as Box<dyn Net>).unwrap_or(Box::new(net_stub) as Box<dyn Net>)
     );
 
     // load kernel
     let mut kern = Kern::new(driver, term);
 
-    // load store
-    writeln!(kern, "INFO vnix: load `vnix.store` storage").unwrap();
-
-    if let Some(store) = load_store() {
-        kern.ram_store.data = kern.new_unit(store);
-    } else {
-        println!("ERR loader: store not available");
-        return;
-    }
+    // store write-through
+    kern.ram_store.sync = env::args().any(|arg| arg == "--sync");
 
     // run
     kern.drv.time.start().unwrap();